@@ -6,10 +6,19 @@ use crate::Frame;
 
 pub trait OutputChannel {
     fn send(&self, data: Frame<Bytes>) -> ();
+
+    /// Returns true once the downstream node has closed the channel and won't read from it
+    /// again, anything sent after that is dropped
+    fn is_closed(&self) -> bool {
+        false
+    }
 }
 
 pub trait InputChannel {
     fn finished(&self) -> bool;
     fn finished_writing(&self) -> bool;
     fn recv(&self) -> Option<Frame<Bytes>>;
+
+    /// Tells the upstream node no more data is wanted from this channel
+    fn close(&self) {}
 }
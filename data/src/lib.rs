@@ -114,6 +114,41 @@ impl MxlData for u32 {
     }
 }
 
+macro_rules! impl_mxl_data_be {
+    ($($ty:ty => $get:ident, $put:ident;)*) => {
+        $(
+            impl MxlData for $ty {
+                fn from_buffer_frame(frame: Frame<Bytes>) -> Frame<Self> {
+                    use bytes::Buf;
+
+                    frame.flat_map(|mut d| {
+                        if d.remaining() < std::mem::size_of::<$ty>() {
+                            return Frame::Error;
+                        }
+
+                        Frame::Data(d.$get())
+                    })
+                }
+
+                fn into_buffer_frame(self) -> Result<Frame<Bytes>, ()> {
+                    use bytes::BufMut;
+
+                    let mut buf = vec![];
+                    buf.$put(self);
+
+                    Ok(Frame::Data(buf.into()))
+                }
+            }
+        )*
+    };
+}
+
+impl_mxl_data_be! {
+    u64 => get_u64, put_u64;
+    i64 => get_i64, put_i64;
+    f64 => get_f64, put_f64;
+}
+
 impl<V: MxlData> MxlData for Vec<V> {
     fn from_buffer_frame(frame: Frame<Bytes>) -> Frame<Self> {
         use bytes::Buf;
@@ -239,6 +274,17 @@ impl<K: MxlData, V: MxlData> MxlData for KV<K, V> {
     }
 }
 
+/// Pairs are encoded the same way as a `KV`
+impl<A: MxlData, B: MxlData> MxlData for (A, B) {
+    fn from_buffer_frame(frame: Frame<Bytes>) -> Frame<Self> {
+        KV::<A, B>::from_buffer_frame(frame).map(KV::into_parts)
+    }
+
+    fn into_buffer_frame(self) -> Result<Frame<Bytes>, ()> {
+        KV(self.0, self.1).into_buffer_frame()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::{Frame, MxlData, KV};
@@ -284,6 +330,18 @@ mod test {
             panic!("frame was not data")
         }
     }
+
+    #[test]
+    fn short_numeric_frame_is_error() {
+        let short = || Frame::Data(bytes::Bytes::from_static(&[0, 1, 2]));
+
+        assert!(matches!(u64::from_buffer_frame(short()), Frame::Error));
+        assert!(matches!(i64::from_buffer_frame(short()), Frame::Error));
+        assert!(matches!(f64::from_buffer_frame(short()), Frame::Error));
+
+        let data = 1.5f64.into_buffer_frame().unwrap();
+        assert!(matches!(f64::from_buffer_frame(data), Frame::Data(v) if v == 1.5));
+    }
 }
 
 #[derive(Debug, Clone)]
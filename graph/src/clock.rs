@@ -0,0 +1,20 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Source of the current time for nodes, e.g. to expire state. The runtime provides its own
+/// clock since guests can't always read the system time.
+pub trait Clock: Send + Sync {
+    /// Seconds since the unix epoch
+    fn now(&self) -> u64;
+}
+
+/// Reads the time from the operating system
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+    }
+}
@@ -1,17 +1,25 @@
 use std::{
+    cmp::Ordering,
     collections::{HashMap, HashSet},
+    hash::Hash,
     marker::PhantomData,
+    mem,
+    sync::Arc,
+    time::Duration,
 };
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use bytes::Bytes;
-use log::error;
+use log::{debug, error, warn};
 use serde::Serialize;
 use mixlayer_data::JsonObject;
 
 use crate::{
-    transform, Frame, InputChannel, OutputChannel, MxlData, MxlLeftJoin, MxlSink, MxlSource, MxlTransform, KV,
+    join, transform, Frame, InputChannel, OutputChannel, MxlData, MxlSink, MxlSource, MxlTransform, KV,
+    Clock, Eviction, JoinConfig, JoinKind, JoinMode, JoinSide, MxlJoin, OperatorNode, StreamOperator,
+    SortConfig, SymmetricHashJoin, SystemClock, Window,
 };
+use crate::transform::{AggregateByKey, Aggregator, CombineValues, Count, DistinctState, Fold, Keep, Reduce};
 
 pub type MxlNodeId = u32;
 
@@ -42,11 +50,16 @@ pub struct VNodeMetadata {
     pub node_type: MxlNodeType,
     pub input_type: String,
     pub output_type: String,
+    pub join_kind: Option<JoinKind>,
+    /// expected number of elements the node outputs, used to plan joins
+    pub size_hint: Option<usize>,
 }
 
 pub struct MxlGraph {
     nodes: HashMap<MxlNodeId, Box<dyn MxlNode + Send>>,
     topo: VGraphTopology,
+    run_state: HashMap<MxlNodeId, NodeRunState>,
+    clock: Arc<dyn Clock>,
 }
 
 impl MxlGraph {
@@ -58,9 +71,16 @@ impl MxlGraph {
                 edges: HashMap::new(),
                 source_ids: HashSet::new(),
             },
+            run_state: HashMap::new(),
+            clock: Arc::new(SystemClock),
         }
     }
 
+    /// Replaces the clock nodes read the time from, the system clock by default
+    pub fn set_clock<C: Clock + 'static>(&mut self, clock: C) {
+        self.clock = Arc::new(clock);
+    }
+
     pub fn edges<'a>(&'a self) -> Box<dyn Iterator<Item = &'a MxlEdge> + 'a> {
         let it = self
             .topo
@@ -135,6 +155,59 @@ impl MxlGraph {
         self.nodes.get(node_id)
     }
 
+    /// Ticks a node with a context holding its input and output channels. Once all of the
+    /// node's inputs have ended, or a source has sent End on all of its outputs, `on_finish` is
+    /// called, End is sent on every output that hasn't had one yet, the node's inputs are closed
+    /// and the node is finished: further calls do nothing. A node whose outputs have all been
+    /// closed downstream is finished without being ticked again.
+    pub fn tick_node(&mut self, node_id: &MxlNodeId, mut ctx: MxlNodeCtx) -> Result<()> {
+        let node = self
+            .nodes
+            .get_mut(node_id)
+            .ok_or_else(|| anyhow!("node {} not found", node_id))?;
+
+        let state = self.run_state.entry(*node_id).or_default();
+
+        if state.finished {
+            return Ok(());
+        }
+
+        // the ctx is rebuilt by the runtime for every tick, so carry the state across in it
+        ctx.state = mem::take(state);
+        ctx.clock = self.clock.clone();
+
+        let mut result = if ctx.downstream_closed() {
+            // nobody reads what this node would emit
+            ctx.stop();
+            Ok(())
+        } else {
+            node.tick(&mut ctx)
+        };
+
+        if result.is_ok() && ctx.should_finish() {
+            // the node is finished even if the hook fails so it's never called twice
+            result = node.on_finish(&mut ctx);
+
+            if result.is_err() {
+                ctx.send_all(Frame::Error);
+            }
+
+            ctx.finish();
+        }
+
+        *state = ctx.state;
+
+        result
+    }
+
+    /// Returns true once a node has finished and no longer needs to be ticked
+    pub fn is_finished(&self, node_id: &MxlNodeId) -> bool {
+        self.run_state
+            .get(node_id)
+            .map(|s| s.finished)
+            .unwrap_or(false)
+    }
+
     pub fn node_operation(&self, node_id: &MxlNodeId) -> Option<&str> {
         self.topo
             .metadata
@@ -152,6 +225,12 @@ impl MxlGraph {
         }
     }
 
+    pub fn size_hint(&mut self, node_id: &MxlNodeId, size_hint: usize) {
+        if let Some(metadata) = self.topo.metadata.get_mut(node_id) {
+            metadata.size_hint = Some(size_hint);
+        }
+    }
+
     fn insert_edge(&mut self, edge: MxlEdge) -> () {
         if self.topo.edges.get(&edge.source_node_id).is_none() {
             self.topo.edges.insert(edge.source_node_id, HashMap::new());
@@ -220,6 +299,8 @@ impl MxlGraph {
             node_type,
             input_type,
             output_type,
+            join_kind: None,
+            size_hint: None,
         };
 
         self.topo.metadata.insert(next_id, metadata);
@@ -255,24 +336,100 @@ impl MxlGraph {
         }
     }
 
-    pub fn left_join<LI, RI, K, LV, RV>(
+    /// Joins two keyed inputs on their keys, `mode` is one of the markers in `join` and decides
+    /// which elements are emitted, e.g. `join::Inner` or `join::Anti`
+    pub fn join<LI, RI, K, LV, RV, M>(
+        &mut self,
+        left: &MxlNodeRef<LI, KV<K, LV>>,
+        right: &MxlNodeRef<RI, KV<K, RV>>,
+        mode: M,
+    ) -> MxlJoinRef<K, LV, RV, M::Output>
+    where
+        K: MxlData + Eq + Hash,
+        LV: MxlData,
+        RV: MxlData,
+        M: JoinMode<LV, RV>,
+    {
+        self.join_with(left, right, mode, JoinConfig::default())
+    }
+
+    /// Like `join`, with control over which side is held in memory and whether it can spill.
+    /// Unless the config picks a build side, the left side is buffered if both inputs have
+    /// size hints and the left one is smaller, otherwise the right side is.
+    pub fn join_with<LI, RI, K, LV, RV, M>(
+        &mut self,
+        left: &MxlNodeRef<LI, KV<K, LV>>,
+        right: &MxlNodeRef<RI, KV<K, RV>>,
+        _mode: M,
+        mut config: JoinConfig,
+    ) -> MxlJoinRef<K, LV, RV, M::Output>
+    where
+        K: MxlData + Eq + Hash,
+        LV: MxlData,
+        RV: MxlData,
+        M: JoinMode<LV, RV>,
+    {
+        if config.build_side.is_none() {
+            let left_hint = self.node_metadata(&left.node_id).and_then(|m| m.size_hint);
+            let right_hint = self.node_metadata(&right.node_id).and_then(|m| m.size_hint);
+
+            if let (Some(left_hint), Some(right_hint)) = (left_hint, right_hint) {
+                if left_hint < right_hint {
+                    config.build_side = Some(JoinSide::Left);
+                }
+            }
+        }
+
+        let join: MxlJoin<K, LV, RV, M> = MxlJoin::with_config(config);
+        self.insert_join::<K, LV, RV, M, _>(left.node_id, right.node_id, join)
+    }
+
+    /// Joins two keyed inputs without waiting for either of them to end, emitting matches as
+    /// soon as both halves have arrived. Both sides are held in memory, bounded by `eviction`;
+    /// unmatched elements are emitted, for modes that want them, when they're evicted.
+    pub fn symmetric_join<LI, RI, K, LV, RV, M>(
         &mut self,
         left: &MxlNodeRef<LI, KV<K, LV>>,
         right: &MxlNodeRef<RI, KV<K, RV>>,
-    ) -> MxlNodeRef<(KV<K, LV>, KV<K, RV>), KV<K, KV<LV, RV>>>
+        _mode: M,
+        eviction: Eviction,
+    ) -> MxlJoinRef<K, LV, RV, M::Output>
     where
-        K: MxlData + PartialEq,
+        K: MxlData + Eq + Hash,
         LV: MxlData,
         RV: MxlData,
+        M: JoinMode<LV, RV>,
     {
-        let join: MxlLeftJoin<K, LV, RV> = MxlLeftJoin::new();
+        let join: SymmetricHashJoin<K, LV, RV, M> = SymmetricHashJoin::new(eviction);
+        self.insert_join::<K, LV, RV, M, _>(left.node_id, right.node_id, join)
+    }
 
-        let left_edge = (left.node_id, 0, crate::join::LEFT_INPUT);
-        let right_edge = (right.node_id, 0, crate::join::RIGHT_INPUT);
+    fn insert_join<K, LV, RV, M, N>(
+        &mut self,
+        left: MxlNodeId,
+        right: MxlNodeId,
+        join: N,
+    ) -> MxlJoinRef<K, LV, RV, M::Output>
+    where
+        K: MxlData + Eq + Hash,
+        LV: MxlData,
+        RV: MxlData,
+        M: JoinMode<LV, RV>,
+        N: MxlNode + Send + 'static,
+    {
+        let left_edge = (left, 0, join::LEFT_INPUT);
+        let right_edge = (right, 0, join::RIGHT_INPUT);
 
-        //TODO figure out how to describe join types in node metadata, using () for now
-        let node_id =
-            self.insert::<(), (), _>(join, Some(&[left_edge, right_edge]), None, MxlNodeType::Join);
+        let node_id = self.insert::<(KV<K, LV>, KV<K, RV>), KV<K, M::Output>, _>(
+            join,
+            Some(&[left_edge, right_edge]),
+            None,
+            MxlNodeType::Join,
+        );
+
+        if let Some(metadata) = self.topo.metadata.get_mut(&node_id) {
+            metadata.join_kind = Some(M::KIND);
+        }
 
         MxlNodeRef {
             node_id,
@@ -280,8 +437,98 @@ impl MxlGraph {
             _out: Default::default(),
         }
     }
+
+    /// Emits `(left, right)` for every pair of elements with the same key
+    pub fn inner_join<LI, RI, K, LV, RV>(
+        &mut self,
+        left: &MxlNodeRef<LI, KV<K, LV>>,
+        right: &MxlNodeRef<RI, KV<K, RV>>,
+    ) -> MxlJoinRef<K, LV, RV, (LV, RV)>
+    where
+        K: MxlData + Eq + Hash,
+        LV: MxlData,
+        RV: MxlData,
+    {
+        self.join(left, right, join::Inner)
+    }
+
+    /// Emits every left element, with `None` if there's no right element with the same key.
+    ///
+    /// This used to return `KV<K, KV<LV, RV>>` from a `MxlLeftJoin` node. That type is gone,
+    /// name the node `MxlJoin<K, LV, RV, join::Left>` and read the value as `(LV, Option<RV>)`.
+    pub fn left_join<LI, RI, K, LV, RV>(
+        &mut self,
+        left: &MxlNodeRef<LI, KV<K, LV>>,
+        right: &MxlNodeRef<RI, KV<K, RV>>,
+    ) -> MxlJoinRef<K, LV, RV, (LV, Option<RV>)>
+    where
+        K: MxlData + Eq + Hash,
+        LV: MxlData,
+        RV: MxlData,
+    {
+        self.join(left, right, join::Left)
+    }
+
+    /// Emits every right element, with `None` if there's no left element with the same key
+    pub fn right_join<LI, RI, K, LV, RV>(
+        &mut self,
+        left: &MxlNodeRef<LI, KV<K, LV>>,
+        right: &MxlNodeRef<RI, KV<K, RV>>,
+    ) -> MxlJoinRef<K, LV, RV, (Option<LV>, RV)>
+    where
+        K: MxlData + Eq + Hash,
+        LV: MxlData,
+        RV: MxlData,
+    {
+        self.join(left, right, join::Right)
+    }
+
+    /// Emits every element of both inputs, with `None` for the side without a match
+    pub fn full_outer_join<LI, RI, K, LV, RV>(
+        &mut self,
+        left: &MxlNodeRef<LI, KV<K, LV>>,
+        right: &MxlNodeRef<RI, KV<K, RV>>,
+    ) -> MxlJoinRef<K, LV, RV, (Option<LV>, Option<RV>)>
+    where
+        K: MxlData + Eq + Hash,
+        LV: MxlData,
+        RV: MxlData,
+    {
+        self.join(left, right, join::FullOuter)
+    }
+
+    /// Emits left elements that have a right element with the same key
+    pub fn semi_join<LI, RI, K, LV, RV>(
+        &mut self,
+        left: &MxlNodeRef<LI, KV<K, LV>>,
+        right: &MxlNodeRef<RI, KV<K, RV>>,
+    ) -> MxlJoinRef<K, LV, RV, LV>
+    where
+        K: MxlData + Eq + Hash,
+        LV: MxlData,
+        RV: MxlData,
+    {
+        self.join(left, right, join::Semi)
+    }
+
+    /// Emits left elements that have no right element with the same key
+    pub fn anti_join<LI, RI, K, LV, RV>(
+        &mut self,
+        left: &MxlNodeRef<LI, KV<K, LV>>,
+        right: &MxlNodeRef<RI, KV<K, RV>>,
+    ) -> MxlJoinRef<K, LV, RV, LV>
+    where
+        K: MxlData + Eq + Hash,
+        LV: MxlData,
+        RV: MxlData,
+    {
+        self.join(left, right, join::Anti)
+    }
 }
 
+/// Reference to a join node, taking keyed elements from both inputs and emitting `KV<K, O>`
+pub type MxlJoinRef<K, LV, RV, O> = MxlNodeRef<(KV<K, LV>, KV<K, RV>), KV<K, O>>;
+
 pub struct MxlNodeRef<In, Out> {
     node_id: MxlNodeId,
     _in: PhantomData<In>,
@@ -326,11 +573,179 @@ impl<In, Out: MxlData> MxlNodeRef<In, Out> {
         self.transform(g, crate::transform::filter(f))
     }
 
+    /// Emits every element of the iterator `f` returns for each element
+    pub fn flat_map<O, It, F>(&self, g: &mut MxlGraph, f: F) -> MxlNodeRef<Out, O>
+    where
+        O: MxlData,
+        It: IntoIterator<Item = O> + Send + Sync + 'static,
+        F: Fn(Out) -> It + Send + Sync + 'static,
+    {
+        self.transform(g, transform::flat_map(f))
+    }
+
+    /// Maps elements with `f`, dropping those it returns `None` for
+    pub fn filter_map<O, F>(&self, g: &mut MxlGraph, f: F) -> MxlNodeRef<Out, O>
+    where
+        O: MxlData,
+        F: Fn(Out) -> Option<O> + Send + Sync + 'static,
+    {
+        self.transform(g, transform::filter_map(f))
+    }
+
+    /// Like `filter_map` with a fallible `f`
+    pub fn try_filter_map<O, F>(&self, g: &mut MxlGraph, f: F) -> MxlNodeRef<Out, O>
+    where
+        O: MxlData,
+        F: Fn(Out) -> Result<Option<O>> + Send + Sync + 'static,
+    {
+        self.transform(g, transform::try_filter_map(f))
+    }
+
+    /// Calls `f` with every element as it passes through, e.g. for logging
+    pub fn inspect<F>(&self, g: &mut MxlGraph, f: F) -> MxlNodeRef<Out, Out>
+    where
+        F: Fn(&Out) + Send + Sync + 'static,
+    {
+        self.transform(g, transform::inspect(f))
+    }
+
+    /// Emits the first `n` elements, then sends End without waiting for the input to end
+    pub fn take(&self, g: &mut MxlGraph, n: usize) -> MxlNodeRef<Out, Out> {
+        self.transform(g, transform::take(n))
+    }
+
+    /// Drops the first `n` elements
+    pub fn skip(&self, g: &mut MxlGraph, n: usize) -> MxlNodeRef<Out, Out> {
+        self.transform(g, transform::skip(n))
+    }
+
+    /// Pairs every element with its position, starting at 0
+    pub fn enumerate(&self, g: &mut MxlGraph) -> MxlNodeRef<Out, KV<u64, Out>> {
+        self.transform(g, transform::enumerate())
+    }
+
+    /// Threads `state` through the elements, emitting what `f` returns for each one
+    pub fn scan<O, S, F>(&self, g: &mut MxlGraph, state: S, f: F) -> MxlNodeRef<Out, O>
+    where
+        O: MxlData,
+        S: Send + Sync + 'static,
+        F: Fn(&mut S, Out) -> O + Send + Sync + 'static,
+    {
+        self.transform(g, transform::scan(state, f))
+    }
+
+    /// Adds a `StreamOperator` that consumes this node's output
+    pub fn operator<Op>(&self, g: &mut MxlGraph, op: Op) -> MxlNodeRef<Out, Op::Output>
+    where
+        Op: StreamOperator<Input = Out> + Sync + Send + 'static,
+    {
+        self.transform(g, OperatorNode::new(op))
+    }
+
+    /// Counts the elements, emitting the total once the input ends
+    pub fn count(&self, g: &mut MxlGraph) -> MxlNodeRef<Out, u64> {
+        self.transform(g, transform::count())
+    }
+
+    /// Drops elements equal to one seen before
+    pub fn distinct(&self, g: &mut MxlGraph, state: DistinctState) -> MxlNodeRef<Out, Out>
+    where
+        Out: Eq + Hash,
+    {
+        self.distinct_by(g, |element: &Out| element.clone(), state)
+    }
+
+    /// Drops elements whose key, as returned by `key_fn`, was seen before
+    pub fn distinct_by<K, F>(
+        &self,
+        g: &mut MxlGraph,
+        key_fn: F,
+        state: DistinctState,
+    ) -> MxlNodeRef<Out, Out>
+    where
+        K: Eq + Hash + Send + Sync + 'static,
+        F: Fn(&Out) -> K + Send + Sync + 'static,
+    {
+        self.transform(g, transform::distinct_by(key_fn, state))
+    }
+
+    /// Keeps one record per key, the first one or the latest one, e.g. for documents that are
+    /// ingested more than once
+    pub fn dedupe_by<K, F>(&self, g: &mut MxlGraph, key_fn: F, keep: Keep) -> MxlNodeRef<Out, Out>
+    where
+        K: Eq + Hash + Send + Sync + 'static,
+        F: Fn(&Out) -> K + Send + Sync + 'static,
+    {
+        self.transform(g, transform::dedupe_by(key_fn, keep))
+    }
+
+    /// Estimates the number of distinct elements in fixed memory, see
+    /// `transform::ApproxCountDistinctXform`
+    pub fn approx_count_distinct(&self, g: &mut MxlGraph, precision: u32) -> MxlNodeRef<Out, u64>
+    where
+        Out: Hash,
+    {
+        self.transform(g, transform::approx_count_distinct(precision))
+    }
+
+    /// Emits the `k` elements with the highest `score_fn`, highest first, once the input ends.
+    /// Only `k` elements are held at a time.
+    pub fn top_k<S, F>(&self, g: &mut MxlGraph, k: usize, score_fn: F) -> MxlNodeRef<Out, Out>
+    where
+        S: PartialOrd + Send + Sync + 'static,
+        F: Fn(&Out) -> S + Send + Sync + 'static,
+    {
+        self.transform(g, transform::top_k(k, score_fn))
+    }
+
+    /// Sorts the elements with `cmp`, emitting them once the input ends. The whole input is
+    /// held in memory, use `sort_with` to spill or to only keep the first elements.
+    pub fn sort_by<C>(&self, g: &mut MxlGraph, cmp: C) -> MxlNodeRef<Out, Out>
+    where
+        C: Fn(&Out, &Out) -> Ordering + Send + Sync + 'static,
+    {
+        self.sort_with(g, cmp, SortConfig::default())
+    }
+
+    /// Sorts the elements by the key `key_fn` returns. The whole input is held in memory, use
+    /// `sort_by_key_with` to spill or to only keep the first elements.
+    pub fn sort_by_key<K, F>(&self, g: &mut MxlGraph, key_fn: F) -> MxlNodeRef<Out, Out>
+    where
+        K: Ord + Send + 'static,
+        F: Fn(&Out) -> K + Send + Sync + 'static,
+    {
+        self.sort_by_key_with(g, key_fn, SortConfig::default())
+    }
+
+    /// Sorts the elements with `cmp`, spilling sorted runs to a `SpillStore` and merging them
+    /// when the input ends, or keeping only the first `limit` elements, as configured
+    pub fn sort_with<C>(&self, g: &mut MxlGraph, cmp: C, config: SortConfig) -> MxlNodeRef<Out, Out>
+    where
+        C: Fn(&Out, &Out) -> Ordering + Send + Sync + 'static,
+    {
+        self.transform(g, transform::sort_by(cmp, config))
+    }
+
+    /// Like `sort_with`, ordering the elements by the key `key_fn` returns. Each element's key
+    /// is computed once rather than on every comparison.
+    pub fn sort_by_key_with<K, F>(
+        &self,
+        g: &mut MxlGraph,
+        key_fn: F,
+        config: SortConfig,
+    ) -> MxlNodeRef<Out, Out>
+    where
+        K: Ord + Send + 'static,
+        F: Fn(&Out) -> K + Send + Sync + 'static,
+    {
+        self.transform(g, transform::sort_by_key(key_fn, config))
+    }
+
     pub fn collect(&self, g: &mut MxlGraph) -> MxlNodeRef<Out, Vec<Out>> {
         self.transform(g, transform::collect())
     }
 
-    pub fn transform<TO, T: MxlTransform<Input = Out, Output = TO> + Sync + Send + 'static>(
+    pub fn transform<TO, T: MxlTransform<Input = Out, Output = TO> + Send + 'static>(
         &self,
         g: &mut MxlGraph,
         transform: T,
@@ -370,6 +785,13 @@ impl<In, Out: MxlData> MxlNodeRef<In, Out> {
         self
     }
 
+    /// Declares roughly how many elements this node outputs so joins can hold the smaller
+    /// input in memory
+    pub fn size_hint(self, g: &mut MxlGraph, size_hint: usize) -> Self {
+        g.size_hint(&self.node_id, size_hint);
+        self
+    }
+
     //TODO probably just get rid of this in favor of connect()
     pub fn connect_sink(&self, g: &mut MxlGraph, sink: &MxlNodeRef<Out, ()>) -> () {
         g.insert_edge(MxlEdge {
@@ -389,10 +811,138 @@ impl<In, Out: MxlData> MxlNodeRef<In, Out> {
         })
     }
 
-    //TODO rename to window
     pub fn batch(&self, g: &mut MxlGraph, batch_size: usize) -> MxlNodeRef<Out, Vec<Out>> {
         self.transform(g, transform::batch(batch_size))
     }
+
+    /// Batches elements, sending a batch once it holds `max_items` elements, its encoded
+    /// elements add up to `max_bytes` or it's been waiting `max_wait`, whichever comes first.
+    /// The graph's clock counts whole seconds, so a `max_wait` under a second is an error.
+    pub fn batch_with(
+        &self,
+        g: &mut MxlGraph,
+        max_items: usize,
+        max_bytes: usize,
+        max_wait: Duration,
+    ) -> Result<MxlNodeRef<Out, Vec<Out>>> {
+        let batch = transform::batch_with(max_items, max_bytes, max_wait)?;
+        Ok(self.transform(g, batch))
+    }
+
+    /// Groups elements into windows of `size` that don't overlap, by the event time in
+    /// milliseconds `timestamp` returns. See `transform::tumbling_window` to allow late data.
+    pub fn tumbling_window<F>(
+        &self,
+        g: &mut MxlGraph,
+        size: Duration,
+        timestamp: F,
+    ) -> MxlNodeRef<Out, Window<Out>>
+    where
+        F: Fn(&Out) -> u64 + Sync + Send + 'static,
+    {
+        self.operator(g, transform::tumbling_window(size, timestamp))
+    }
+
+    /// Groups elements into windows of `size` starting every `slide`, by event time
+    pub fn sliding_window<F>(
+        &self,
+        g: &mut MxlGraph,
+        size: Duration,
+        slide: Duration,
+        timestamp: F,
+    ) -> MxlNodeRef<Out, Window<Out>>
+    where
+        F: Fn(&Out) -> u64 + Sync + Send + 'static,
+    {
+        self.operator(g, transform::sliding_window(size, slide, timestamp))
+    }
+
+    /// Groups elements into sessions that close once no element arrived for `gap` of event time
+    pub fn session_window<F>(
+        &self,
+        g: &mut MxlGraph,
+        gap: Duration,
+        timestamp: F,
+    ) -> MxlNodeRef<Out, Window<Out>>
+    where
+        F: Fn(&Out) -> u64 + Sync + Send + 'static,
+    {
+        self.operator(g, transform::session_window(gap, timestamp))
+    }
+}
+
+/// Number of keys the partial stage of `combine_by_key` holds before sending its combiners on
+const PARTIAL_COMBINE_MAX_KEYS: usize = 1024;
+
+impl<In, K, V> MxlNodeRef<In, KV<K, V>>
+where
+    K: MxlData + Eq + Hash,
+    V: MxlData,
+{
+    /// Aggregates the values of each key with `aggregator`, emitting one element per key once
+    /// the input ends
+    pub fn aggregate_by_key<A>(
+        &self,
+        g: &mut MxlGraph,
+        aggregator: A,
+    ) -> MxlNodeRef<KV<K, V>, KV<K, A::Output>>
+    where
+        A: Aggregator<V>,
+    {
+        self.transform(g, transform::aggregate_by_key(aggregator))
+    }
+
+    /// Combines the values of each key with `f`
+    pub fn reduce_by_key<F>(&self, g: &mut MxlGraph, f: F) -> MxlNodeRef<KV<K, V>, KV<K, V>>
+    where
+        F: Fn(V, V) -> V + Send + Sync + 'static,
+    {
+        self.transform(g, AggregateByKey::new(Reduce(f)).with_label("ReduceByKey"))
+    }
+
+    /// Folds the values of each key into a copy of `init`
+    pub fn fold_by_key<A, F>(
+        &self,
+        g: &mut MxlGraph,
+        init: A,
+        f: F,
+    ) -> MxlNodeRef<KV<K, V>, KV<K, A>>
+    where
+        A: MxlData + Sync,
+        F: Fn(A, V) -> A + Send + Sync + 'static,
+    {
+        self.transform(g, AggregateByKey::new(Fold::new(init, f)).with_label("FoldByKey"))
+    }
+
+    /// Counts the values of each key
+    pub fn count_by_key(&self, g: &mut MxlGraph) -> MxlNodeRef<KV<K, V>, KV<K, u64>> {
+        self.transform(g, AggregateByKey::new(Count).with_label("CountByKey"))
+    }
+
+    /// Aggregates each key in two stages. The first builds a combiner per key with `create` and
+    /// `merge_value`, sending its combiners on whenever it holds too many keys, and the second
+    /// merges the combiners of each key with `merge_combiners`.
+    pub fn combine_by_key<C, Cr, Mv, Mc>(
+        &self,
+        g: &mut MxlGraph,
+        create: Cr,
+        merge_value: Mv,
+        merge_combiners: Mc,
+    ) -> MxlNodeRef<KV<K, C>, KV<K, C>>
+    where
+        C: MxlData,
+        Cr: Fn(V) -> C + Send + Sync + 'static,
+        Mv: Fn(C, V) -> C + Send + Sync + 'static,
+        Mc: Fn(C, C) -> C + Send + Sync + 'static,
+    {
+        let partial = AggregateByKey::new(CombineValues::new(create, merge_value))
+            .partial(PARTIAL_COMBINE_MAX_KEYS)
+            .with_label("CombineByKey[partial]");
+
+        let merge = AggregateByKey::new(Reduce(merge_combiners)).with_label("CombineByKey[merge]");
+
+        self.transform(g, partial).transform(g, merge)
+    }
 }
 
 impl<In, Out: MxlData> MxlNodeRef<In, Vec<Out>> {
@@ -408,12 +958,33 @@ pub trait MxlNode {
     }
 
     fn tick(&mut self, ctx: &mut MxlNodeCtx) -> Result<(), anyhow::Error>;
+
+    /// Called exactly once by the runtime when the node finishes, after every upstream channel
+    /// has sent End, or for sources after End was sent on every output. Frames sent here still
+    /// go out before the End the runtime forwards downstream.
+    fn on_finish(&mut self, _ctx: &mut MxlNodeCtx) -> Result<(), anyhow::Error> {
+        Ok(())
+    }
+}
+
+/// Bookkeeping the runtime keeps for each node between ticks
+#[derive(Default)]
+pub(crate) struct NodeRunState {
+    /// number of End frames received per input port
+    ends_received: HashMap<u32, usize>,
+    /// output ports End has already been sent on
+    ended_outputs: HashSet<u32>,
+    /// set by `MxlNodeCtx::stop`
+    stop_requested: bool,
+    finished: bool,
 }
 
 pub struct MxlNodeCtx {
     //TODO make private
     pub outputs: HashMap<u32, Output>,
     pub inputs: HashMap<u32, Input>,
+    state: NodeRunState,
+    clock: Arc<dyn Clock>,
 }
 
 impl MxlNodeCtx {
@@ -421,11 +992,33 @@ impl MxlNodeCtx {
         Self {
             outputs: HashMap::new(),
             inputs: HashMap::new(),
+            state: NodeRunState::default(),
+            clock: Arc::new(SystemClock),
         }
     }
 
+    /// Current time in seconds since the unix epoch, according to the graph's clock
+    pub fn now(&self) -> u64 {
+        self.clock.now()
+    }
+
+    /// Sends a frame on an output port. End is only sent once per port, anything sent after it
+    /// is dropped.
     pub(crate) fn send(&mut self, output_idx: u32, data: Frame<Bytes>) -> () {
+        if self.state.ended_outputs.contains(&output_idx) {
+            match data {
+                Frame::End => debug!("output {} already ended", output_idx),
+                data => warn!("tried to send {:?} after End on output {}", data, output_idx),
+            }
+
+            return;
+        }
+
         if let Some(output) = self.outputs.get_mut(&output_idx) {
+            if let Frame::End = data {
+                self.state.ended_outputs.insert(output_idx);
+            }
+
             output.send(data)
         } else {
             error!("invalid output index :{}", output_idx); //TODO return error
@@ -435,16 +1028,95 @@ impl MxlNodeCtx {
 
     pub(crate) fn recv(&mut self, input_idx: u32) -> Option<Frame<Bytes>> {
         if let Some(input) = self.inputs.get_mut(&input_idx) {
-            input.recv()
+            let frame = input.recv();
+
+            if let Some(Frame::End) = frame {
+                *self.state.ends_received.entry(input_idx).or_insert(0) += 1;
+            }
+
+            frame
         } else {
             error!("invalid input index"); //TODO return error
             None
         }
     }
 
+    /// Returns true once every upstream channel connected to an input port has sent End
+    pub fn input_ended(&self, input_idx: u32) -> bool {
+        let channels = self.num_input_channels(input_idx);
+        let ends = self.state.ends_received.get(&input_idx).copied().unwrap_or(0);
+
+        channels > 0 && ends >= channels
+    }
+
+    /// Returns true once every input port has ended, always false for sources
+    pub fn inputs_ended(&self) -> bool {
+        !self.inputs.is_empty() && self.inputs.keys().all(|idx| self.input_ended(*idx))
+    }
+
+    /// Returns true if End has been sent on an output port
+    pub fn output_ended(&self, output_idx: u32) -> bool {
+        self.state.ended_outputs.contains(&output_idx)
+    }
+
+    /// Finishes the node after this tick even though its inputs haven't ended, e.g. once it has
+    /// emitted everything it's going to, or a sink that can't write anymore. `on_finish` is still
+    /// called, End is sent downstream and the inputs are closed so upstream nodes stop as well.
+    pub fn stop(&mut self) {
+        self.state.stop_requested = true;
+    }
+
+    /// Returns true once every output channel has been closed by the nodes downstream, so
+    /// nothing this node emits will be read. Always false for sinks.
+    pub fn downstream_closed(&self) -> bool {
+        !self.outputs.is_empty() && self.outputs.values().all(|o| o.is_closed())
+    }
+
+    fn should_finish(&self) -> bool {
+        if self.state.stop_requested {
+            true
+        } else if self.inputs.is_empty() {
+            !self.outputs.is_empty() && self.outputs.keys().all(|idx| self.output_ended(*idx))
+        } else {
+            self.inputs_ended()
+        }
+    }
+
+    fn send_all(&mut self, data: Frame<Bytes>) {
+        let mut output_idxs: Vec<u32> = self.outputs.keys().copied().collect();
+        output_idxs.sort();
+
+        for output_idx in output_idxs {
+            self.send(output_idx, data.clone());
+        }
+    }
+
+    fn finish(&mut self) {
+        self.send_all(Frame::End);
+
+        // let upstream nodes stop early if this was the only reader of their output
+        for input in self.inputs.values() {
+            input.close();
+        }
+
+        self.state.finished = true;
+    }
+
     pub fn recv_finished(&self) -> bool {
         self.inputs.values().all(|i| i.finished())
     }
+
+    /// Number of upstream channels connected to an input port
+    pub fn num_input_channels(&self, input_idx: u32) -> usize {
+        self.inputs
+            .get(&input_idx)
+            .map(|i| i.num_channels())
+            .unwrap_or(0)
+    }
+
+    pub fn has_output(&self, output_idx: u32) -> bool {
+        self.outputs.contains_key(&output_idx)
+    }
 }
 
 pub struct Output {
@@ -457,6 +1129,11 @@ impl Output {
             ch.send(data.clone())
         }
     }
+
+    /// True once every channel of this output has been closed by its downstream node
+    pub fn is_closed(&self) -> bool {
+        !self.output_chs.is_empty() && self.output_chs.iter().all(|ch| ch.is_closed())
+    }
 }
 
 pub struct Input {
@@ -486,6 +1163,17 @@ impl Input {
     pub fn finished(&self) -> bool {
         self.input_chs.iter().all(|ch| ch.finished())
     }
+
+    pub fn num_channels(&self) -> usize {
+        self.input_chs.len()
+    }
+
+    /// Tells the upstream nodes feeding this input that no more data is wanted
+    pub fn close(&self) {
+        for ch in self.input_chs.iter() {
+            ch.close();
+        }
+    }
 }
 
 pub fn format_node_type(ty: &str) -> String {
@@ -504,3 +1192,115 @@ pub fn format_node_type(ty: &str) -> String {
     //fall through here and return input if failed
     ty.to_owned()
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use super::*;
+    use crate::source::vec_source;
+    use crate::test_util::{ctx, drain, push, tick, Frames};
+
+    #[test]
+    fn end_is_forwarded_once_after_all_inputs_end() {
+        let mut g = MxlGraph::new();
+        let count = g.transform(transform::count::<String>());
+
+        let left: Frames = Default::default();
+        let right: Frames = Default::default();
+        let output: Frames = Default::default();
+
+        push(&left, vec!["a".to_owned()]);
+        push(&right, vec!["a".to_owned()]);
+
+        tick(&mut g, count.id(), &[(0, &left), (0, &right)], &output, 10);
+
+        assert!(g.is_finished(&count.id()));
+        assert!(matches!(drain::<u64>(&output).as_slice(), [Frame::Data(2), Frame::End]));
+    }
+
+    #[test]
+    fn source_finishes_after_sending_end() {
+        let mut g = MxlGraph::new();
+        let source = g.source(vec_source(vec!["a".to_owned()]));
+
+        let output: Frames = Default::default();
+
+        tick(&mut g, source.id(), &[], &output, 5);
+
+        assert!(g.is_finished(&source.id()));
+        assert_eq!(output.borrow().len(), 2);
+    }
+
+    /// An edge whose reader can close it, frames sent after that are dropped
+    #[derive(Clone, Default)]
+    struct ClosableEdge(Frames, Rc<Cell<bool>>);
+
+    impl InputChannel for ClosableEdge {
+        fn finished(&self) -> bool {
+            self.0.borrow().is_empty()
+        }
+
+        fn finished_writing(&self) -> bool {
+            true
+        }
+
+        fn recv(&self) -> Option<Frame<Bytes>> {
+            self.0.borrow_mut().pop_front()
+        }
+
+        fn close(&self) {
+            self.1.set(true);
+            self.0.borrow_mut().clear();
+        }
+    }
+
+    impl OutputChannel for ClosableEdge {
+        fn send(&self, data: Frame<Bytes>) {
+            if !self.1.get() {
+                self.0.borrow_mut().push_back(data)
+            }
+        }
+
+        fn is_closed(&self) -> bool {
+            self.1.get()
+        }
+    }
+
+    #[test]
+    fn finished_node_closes_input_and_stops_upstream() {
+        let mut g = MxlGraph::new();
+        let source = g.source(vec_source(vec!["a".to_owned(), "b".to_owned()]));
+        let take = source.take(&mut g, 1);
+
+        let edge = ClosableEdge::default();
+        let output: Frames = Default::default();
+
+        let source_ctx = |edge: &ClosableEdge| {
+            let mut ctx = MxlNodeCtx::new();
+            ctx.outputs = HashMap::from([(
+                0,
+                Output {
+                    output_chs: vec![Box::new(edge.clone())],
+                },
+            )]);
+            ctx
+        };
+
+        g.tick_node(&source.id(), source_ctx(&edge)).unwrap();
+
+        let mut take_ctx = ctx(&[], &output);
+        take_ctx.inputs = HashMap::from([(0, Input::new(vec![Box::new(edge.clone())]))]);
+        g.tick_node(&take.id(), take_ctx).unwrap();
+
+        assert!(g.is_finished(&take.id()));
+        assert!(edge.1.get(), "take closes its input once finished");
+
+        let ctx = source_ctx(&edge);
+        assert!(ctx.downstream_closed());
+        g.tick_node(&source.id(), ctx).unwrap();
+
+        assert!(g.is_finished(&source.id()));
+        assert!(edge.0.borrow().is_empty());
+    }
+}
@@ -0,0 +1,840 @@
+use crate::graph::{MxlNode, MxlNodeCtx};
+use crate::spill::{read_frame, run_prefix, write_frame, SpillStore};
+use crate::Result;
+use crate::{Frame, MxlData, KV};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::marker::PhantomData;
+
+use anyhow::anyhow;
+use bytes::Bytes;
+
+mod symmetric;
+
+pub use symmetric::{Eviction, SymmetricHashJoin};
+
+pub const LEFT_INPUT: u32 = 0;
+pub const RIGHT_INPUT: u32 = 1;
+
+pub trait VJoin: MxlNode {
+    type K: MxlData + Eq + Hash;
+    type LV: MxlData;
+    type RV: MxlData;
+    type Output: MxlData;
+
+    fn recv_left(&self, ctx: &mut MxlNodeCtx) -> Option<Frame<KV<Self::K, Self::LV>>> {
+        if let Some(data) = ctx.recv(LEFT_INPUT) {
+            Some(KV::from_buffer_frame(data))
+        } else {
+            None
+        }
+    }
+
+    fn recv_right(&self, ctx: &mut MxlNodeCtx) -> Option<Frame<KV<Self::K, Self::RV>>> {
+        if let Some(data) = ctx.recv(RIGHT_INPUT) {
+            Some(KV::from_buffer_frame(data))
+        } else {
+            None
+        }
+    }
+
+    fn send(&self, ctx: &mut MxlNodeCtx, data: Frame<Self::Output>) -> Result<()> {
+        let data = match data {
+            Frame::Data(d) => d
+                .into_buffer_frame()
+                .map_err(|_| anyhow!("error serializing frame"))?,
+            Frame::End => Frame::End,
+            Frame::Error => Frame::Error,
+        };
+
+        ctx.send(0, data);
+
+        Ok(())
+    }
+}
+
+/// Describes a join in node metadata
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinKind {
+    Inner,
+    Left,
+    Right,
+    FullOuter,
+    Semi,
+    Anti,
+}
+
+/// Decides what a join emits for matching pairs and for elements that never matched. Every
+/// method emits nothing by default.
+pub trait JoinMode<LV: MxlData, RV: MxlData>: Send + Sync + 'static {
+    type Output: MxlData;
+
+    const KIND: JoinKind;
+
+    /// Called for every pair of left and right values with the same key
+    fn matched(_left: &LV, _right: &RV) -> Option<Self::Output> {
+        None
+    }
+
+    /// Called once for every left value that matched at least one right value
+    fn matched_left(_left: &LV) -> Option<Self::Output> {
+        None
+    }
+
+    /// Called for every left value no right value matched
+    fn unmatched_left(_left: &LV) -> Option<Self::Output> {
+        None
+    }
+
+    /// Called for every right value no left value matched
+    fn unmatched_right(_right: &RV) -> Option<Self::Output> {
+        None
+    }
+}
+
+/// Emits a pair for every left and right element with the same key
+pub struct Inner;
+
+/// Emits every left element, paired with each matching right value or `None`
+pub struct Left;
+
+/// Emits every right element, paired with each matching left value or `None`
+pub struct Right;
+
+/// Emits every element from both sides, with `None` for the side that had no match
+pub struct FullOuter;
+
+/// Emits left elements that have at least one match, once each
+pub struct Semi;
+
+/// Emits left elements that have no match
+pub struct Anti;
+
+impl<LV: MxlData, RV: MxlData> JoinMode<LV, RV> for Inner {
+    type Output = (LV, RV);
+
+    const KIND: JoinKind = JoinKind::Inner;
+
+    fn matched(left: &LV, right: &RV) -> Option<Self::Output> {
+        Some((left.clone(), right.clone()))
+    }
+}
+
+impl<LV: MxlData, RV: MxlData> JoinMode<LV, RV> for Left {
+    type Output = (LV, Option<RV>);
+
+    const KIND: JoinKind = JoinKind::Left;
+
+    fn matched(left: &LV, right: &RV) -> Option<Self::Output> {
+        Some((left.clone(), Some(right.clone())))
+    }
+
+    fn unmatched_left(left: &LV) -> Option<Self::Output> {
+        Some((left.clone(), None))
+    }
+}
+
+impl<LV: MxlData, RV: MxlData> JoinMode<LV, RV> for Right {
+    type Output = (Option<LV>, RV);
+
+    const KIND: JoinKind = JoinKind::Right;
+
+    fn matched(left: &LV, right: &RV) -> Option<Self::Output> {
+        Some((Some(left.clone()), right.clone()))
+    }
+
+    fn unmatched_right(right: &RV) -> Option<Self::Output> {
+        Some((None, right.clone()))
+    }
+}
+
+impl<LV: MxlData, RV: MxlData> JoinMode<LV, RV> for FullOuter {
+    type Output = (Option<LV>, Option<RV>);
+
+    const KIND: JoinKind = JoinKind::FullOuter;
+
+    fn matched(left: &LV, right: &RV) -> Option<Self::Output> {
+        Some((Some(left.clone()), Some(right.clone())))
+    }
+
+    fn unmatched_left(left: &LV) -> Option<Self::Output> {
+        Some((Some(left.clone()), None))
+    }
+
+    fn unmatched_right(right: &RV) -> Option<Self::Output> {
+        Some((None, Some(right.clone())))
+    }
+}
+
+impl<LV: MxlData, RV: MxlData> JoinMode<LV, RV> for Semi {
+    type Output = LV;
+
+    const KIND: JoinKind = JoinKind::Semi;
+
+    fn matched_left(left: &LV) -> Option<Self::Output> {
+        Some(left.clone())
+    }
+}
+
+impl<LV: MxlData, RV: MxlData> JoinMode<LV, RV> for Anti {
+    type Output = LV;
+
+    const KIND: JoinKind = JoinKind::Anti;
+
+    fn unmatched_left(left: &LV) -> Option<Self::Output> {
+        Some(left.clone())
+    }
+}
+
+/// The input a join holds in memory, the other input is streamed past it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinSide {
+    Left,
+    Right,
+}
+
+impl JoinSide {
+    fn port(&self) -> u32 {
+        match self {
+            JoinSide::Left => LEFT_INPUT,
+            JoinSide::Right => RIGHT_INPUT,
+        }
+    }
+
+    fn other(&self) -> JoinSide {
+        match self {
+            JoinSide::Left => JoinSide::Right,
+            JoinSide::Right => JoinSide::Left,
+        }
+    }
+}
+
+/// Tunes how a join holds its state
+pub struct JoinConfig {
+    pub(crate) build_side: Option<JoinSide>,
+    spill: Option<Box<dyn SpillStore>>,
+    max_build_rows: usize,
+    partitions: usize,
+}
+
+impl Default for JoinConfig {
+    fn default() -> Self {
+        Self {
+            build_side: None,
+            spill: None,
+            max_build_rows: usize::MAX,
+            partitions: 16,
+        }
+    }
+}
+
+impl JoinConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Buffers `side` in memory. By default the right side is buffered unless size hints on
+    /// both inputs say the left side is smaller.
+    pub fn build_side(mut self, side: JoinSide) -> Self {
+        self.build_side = Some(side);
+        self
+    }
+
+    /// Once more than `max_build_rows` elements are buffered, partitions both inputs by key
+    /// into `store` and joins one partition at a time after both inputs have ended
+    pub fn spill<S: SpillStore + 'static>(mut self, store: S, max_build_rows: usize) -> Self {
+        self.spill = Some(Box::new(store));
+        self.max_build_rows = max_build_rows;
+        self
+    }
+
+    /// Number of partitions used when spilling, defaults to 16
+    pub fn partitions(mut self, partitions: usize) -> Self {
+        self.partitions = partitions.max(1);
+        self
+    }
+}
+
+/// Buffered elements of the build side, keyed for lookup. Each value remembers whether
+/// anything on the probe side matched it.
+struct JoinTable<K, V> {
+    entries: HashMap<K, Vec<(V, bool)>>,
+    len: usize,
+}
+
+impl<K: MxlData + Eq + Hash, V: MxlData> JoinTable<K, V> {
+    fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+            len: 0,
+        }
+    }
+
+    fn insert(&mut self, kv: KV<K, V>) {
+        let (key, value) = kv.into_parts();
+        self.entries.entry(key).or_default().push((value, false));
+        self.len += 1;
+    }
+
+    fn matches(&mut self, key: &K) -> &mut [(V, bool)] {
+        match self.entries.get_mut(key) {
+            Some(values) => values.as_mut_slice(),
+            None => &mut [],
+        }
+    }
+
+    /// Removes every element, returning them along with whether they were matched
+    fn drain(&mut self) -> Vec<(KV<K, V>, bool)> {
+        self.len = 0;
+
+        self.entries
+            .drain()
+            .flat_map(|(key, values)| {
+                values
+                    .into_iter()
+                    .map(move |(value, matched)| (KV(key.clone(), value), matched))
+            })
+            .collect()
+    }
+}
+
+/// Partitions the build and probe sides were written to once the build side outgrew memory
+struct JoinSpill {
+    store: Box<dyn SpillStore>,
+    prefix: String,
+    build: Vec<Box<dyn Write + Send>>,
+    probe: Vec<Box<dyn Write + Send>>,
+}
+
+impl JoinSpill {
+    fn new(mut store: Box<dyn SpillStore>, partitions: usize) -> Result<Self> {
+        let prefix = run_prefix("join");
+
+        let mut build = Vec::with_capacity(partitions);
+        let mut probe = Vec::with_capacity(partitions);
+
+        for partition in 0..partitions {
+            build.push(store.create(&Self::run_name(&prefix, "build", partition))?);
+            probe.push(store.create(&Self::run_name(&prefix, "probe", partition))?);
+        }
+
+        Ok(Self {
+            store,
+            prefix,
+            build,
+            probe,
+        })
+    }
+
+    fn run_name(prefix: &str, side: &str, partition: usize) -> String {
+        format!("{}-{}-{}", prefix, side, partition)
+    }
+
+    fn partition<K: Hash>(&self, key: &K) -> usize {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        (hasher.finish() % self.build.len() as u64) as usize
+    }
+
+    fn write<K: MxlData + Hash, V: MxlData>(&mut self, probe: bool, kv: KV<K, V>) -> Result<()> {
+        let partition = self.partition(kv.key());
+
+        let frame = kv
+            .into_buffer_frame()
+            .map_err(|_| anyhow!("error serializing spilled join element"))?;
+
+        let runs = if probe {
+            &mut self.probe
+        } else {
+            &mut self.build
+        };
+        write_frame(runs[partition].as_mut(), frame)
+    }
+
+    /// Reads back a spilled partition, removing the run
+    fn read<K: MxlData, V: MxlData>(
+        &mut self,
+        side: &str,
+        partition: usize,
+    ) -> Result<Vec<KV<K, V>>> {
+        let name = Self::run_name(&self.prefix, side, partition);
+        let mut run = self.store.open(&name)?;
+        let mut out = Vec::new();
+
+        while let Some(frame) = read_frame(run.as_mut())? {
+            if let Frame::Data(kv) = KV::<K, V>::from_buffer_frame(frame) {
+                out.push(kv);
+            }
+        }
+
+        self.store.remove(&name)?;
+
+        Ok(out)
+    }
+}
+
+/// Joins two keyed inputs. One input, the build side, is held in a hash table until it ends,
+/// then elements of the other input are matched against it as they arrive. Elements of the
+/// build side that never matched are emitted, for modes that want them, once both inputs have
+/// ended. Use `SymmetricHashJoin` when neither input is guaranteed to end.
+pub struct MxlJoin<K: MxlData, L: MxlData, R: MxlData, M: JoinMode<L, R>> {
+    _mode: PhantomData<M>,
+    build_side: JoinSide,
+    /// only used when building on the left
+    left_table: JoinTable<K, L>,
+    /// only used when building on the right
+    right_table: JoinTable<K, R>,
+    buffering: bool,
+    spill_store: Option<Box<dyn SpillStore>>,
+    max_build_rows: usize,
+    partitions: usize,
+    /// set once the build side outgrew `max_build_rows`
+    spill: Option<JoinSpill>,
+}
+
+impl<K, L, R, M> VJoin for MxlJoin<K, L, R, M>
+where
+    K: MxlData + Eq + Hash,
+    L: MxlData,
+    R: MxlData,
+    M: JoinMode<L, R>,
+{
+    type K = K;
+    type LV = L;
+    type RV = R;
+    type Output = KV<Self::K, M::Output>;
+}
+
+impl<K, L, R, M> MxlNode for MxlJoin<K, L, R, M>
+where
+    K: MxlData + Eq + Hash,
+    L: MxlData,
+    R: MxlData,
+    M: JoinMode<L, R>,
+{
+    fn tick(&mut self, ctx: &mut MxlNodeCtx) -> Result<()> {
+        if self.buffering {
+            let build_port = self.build_side.port();
+
+            match ctx.recv(build_port) {
+                Some(Frame::Data(data)) => self.build(data)?,
+                Some(Frame::Error) => self.send(ctx, Frame::Error)?,
+                Some(Frame::End) => self.buffering = !ctx.input_ended(build_port),
+                None => (),
+            }
+        }
+
+        if !self.buffering {
+            match ctx.recv(self.build_side.other().port()) {
+                Some(Frame::Data(data)) => {
+                    for kv in self.probe(data)? {
+                        self.send(ctx, Frame::Data(kv))?;
+                    }
+                }
+                Some(Frame::Error) => self.send(ctx, Frame::Error)?,
+                _ => (),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn on_finish(&mut self, ctx: &mut MxlNodeCtx) -> Result<()> {
+        let mut out = Vec::new();
+
+        match self.spill.take() {
+            Some(spill) => self.join_spilled(spill, |kv| out.push(kv))?,
+            None => self.drain_unmatched(&mut out),
+        }
+
+        for kv in out {
+            self.send(ctx, Frame::Data(kv))?;
+        }
+
+        Ok(())
+    }
+
+    fn default_label(&self) -> Option<String> {
+        Some(format!("{:?}Join", M::KIND))
+    }
+}
+
+impl<K, L, R, M> Default for MxlJoin<K, L, R, M>
+where
+    K: MxlData + Eq + Hash,
+    L: MxlData,
+    R: MxlData,
+    M: JoinMode<L, R>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, L, R, M> MxlJoin<K, L, R, M>
+where
+    K: MxlData + Eq + Hash,
+    L: MxlData,
+    R: MxlData,
+    M: JoinMode<L, R>,
+{
+    pub fn new() -> Self {
+        Self::with_config(JoinConfig::default())
+    }
+
+    pub fn with_config(config: JoinConfig) -> Self {
+        Self {
+            _mode: Default::default(),
+            build_side: config.build_side.unwrap_or(JoinSide::Right),
+            left_table: JoinTable::new(),
+            right_table: JoinTable::new(),
+            buffering: true,
+            spill_store: config.spill,
+            max_build_rows: config.max_build_rows,
+            partitions: config.partitions,
+            spill: None,
+        }
+    }
+
+    /// Buffers an element of the build side
+    fn build(&mut self, data: Bytes) -> Result<()> {
+        match self.build_side {
+            JoinSide::Left => {
+                if let Frame::Data(kv) = KV::<K, L>::from_buffer_frame(Frame::Data(data)) {
+                    match self.spill.as_mut() {
+                        Some(spill) => return spill.write(false, kv),
+                        None => self.left_table.insert(kv),
+                    }
+                }
+            }
+            JoinSide::Right => {
+                if let Frame::Data(kv) = KV::<K, R>::from_buffer_frame(Frame::Data(data)) {
+                    match self.spill.as_mut() {
+                        Some(spill) => return spill.write(false, kv),
+                        None => self.right_table.insert(kv),
+                    }
+                }
+            }
+        }
+
+        self.spill_if_full()
+    }
+
+    /// Moves the build side to the spill store once it's too big to keep in memory
+    fn spill_if_full(&mut self) -> Result<()> {
+        if self.left_table.len + self.right_table.len <= self.max_build_rows {
+            return Ok(());
+        }
+
+        let store = match self.spill_store.take() {
+            Some(store) => store,
+            None => return Ok(()),
+        };
+
+        let mut spill = JoinSpill::new(store, self.partitions)?;
+
+        for (kv, _) in self.left_table.drain() {
+            spill.write(false, kv)?;
+        }
+
+        for (kv, _) in self.right_table.drain() {
+            spill.write(false, kv)?;
+        }
+
+        self.spill = Some(spill);
+
+        Ok(())
+    }
+
+    /// Matches an element of the probe side against the build side, or spills it if the build
+    /// side was spilled
+    fn probe(&mut self, data: Bytes) -> Result<Vec<KV<K, M::Output>>> {
+        let mut out = Vec::new();
+
+        match self.build_side {
+            JoinSide::Left => {
+                if let Frame::Data(kv) = KV::<K, R>::from_buffer_frame(Frame::Data(data)) {
+                    match self.spill.as_mut() {
+                        Some(spill) => spill.write(true, kv)?,
+                        None => Self::probe_right_in(&mut self.left_table, kv, &mut out),
+                    }
+                }
+            }
+            JoinSide::Right => {
+                if let Frame::Data(kv) = KV::<K, L>::from_buffer_frame(Frame::Data(data)) {
+                    match self.spill.as_mut() {
+                        Some(spill) => spill.write(true, kv)?,
+                        None => Self::probe_left_in(&mut self.right_table, kv, &mut out),
+                    }
+                }
+            }
+        }
+
+        Ok(out)
+    }
+
+    /// Matches a left element against right elements held in `table`
+    fn probe_left_in(table: &mut JoinTable<K, R>, left: KV<K, L>, out: &mut Vec<KV<K, M::Output>>) {
+        let (key, left) = left.into_parts();
+        let matches = table.matches(&key);
+
+        if matches.is_empty() {
+            out.extend(M::unmatched_left(&left).map(|o| KV(key.clone(), o)));
+            return;
+        }
+
+        for (right, matched) in matches.iter_mut() {
+            *matched = true;
+            out.extend(M::matched(&left, right).map(|o| KV(key.clone(), o)));
+        }
+
+        out.extend(M::matched_left(&left).map(|o| KV(key.clone(), o)));
+    }
+
+    /// Matches a right element against left elements held in `table`
+    fn probe_right_in(
+        table: &mut JoinTable<K, L>,
+        right: KV<K, R>,
+        out: &mut Vec<KV<K, M::Output>>,
+    ) {
+        let (key, right) = right.into_parts();
+        let matches = table.matches(&key);
+
+        if matches.is_empty() {
+            out.extend(M::unmatched_right(&right).map(|o| KV(key.clone(), o)));
+            return;
+        }
+
+        for (left, matched) in matches.iter_mut() {
+            out.extend(M::matched(left, &right).map(|o| KV(key.clone(), o)));
+
+            if !*matched {
+                *matched = true;
+                out.extend(M::matched_left(left).map(|o| KV(key.clone(), o)));
+            }
+        }
+    }
+
+    /// Emits build side elements that were never matched
+    fn drain_unmatched(&mut self, out: &mut Vec<KV<K, M::Output>>) {
+        for (KV(key, left), matched) in self.left_table.drain() {
+            if !matched {
+                out.extend(M::unmatched_left(&left).map(|o| KV(key, o)));
+            }
+        }
+
+        for (KV(key, right), matched) in self.right_table.drain() {
+            if !matched {
+                out.extend(M::unmatched_right(&right).map(|o| KV(key, o)));
+            }
+        }
+    }
+
+    /// Joins the spilled inputs one partition at a time, loading each build partition into
+    /// memory and streaming the matching probe partition past it
+    fn join_spilled<F: FnMut(KV<K, M::Output>)>(
+        &mut self,
+        mut spill: JoinSpill,
+        mut emit: F,
+    ) -> Result<()> {
+        for run in spill.build.iter_mut().chain(spill.probe.iter_mut()) {
+            run.flush()?;
+        }
+
+        let partitions = spill.build.len();
+        spill.build.clear();
+        spill.probe.clear();
+
+        for partition in 0..partitions {
+            let mut out = Vec::new();
+
+            match self.build_side {
+                JoinSide::Left => {
+                    for kv in spill.read::<K, L>("build", partition)? {
+                        self.left_table.insert(kv);
+                    }
+
+                    for kv in spill.read::<K, R>("probe", partition)? {
+                        Self::probe_right_in(&mut self.left_table, kv, &mut out);
+                    }
+                }
+                JoinSide::Right => {
+                    for kv in spill.read::<K, R>("build", partition)? {
+                        self.right_table.insert(kv);
+                    }
+
+                    for kv in spill.read::<K, L>("probe", partition)? {
+                        Self::probe_left_in(&mut self.right_table, kv, &mut out);
+                    }
+                }
+            }
+
+            self.drain_unmatched(&mut out);
+            out.into_iter().for_each(&mut emit);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::vec_source;
+    use crate::test_util::{data, drain, push, tick, Frames};
+    use crate::MxlGraph;
+
+    #[test]
+    fn full_outer_join_emits_unmatched_from_both_sides() {
+        let mut g = MxlGraph::new();
+        let left = g.source(vec_source(Vec::<KV<String, String>>::new()));
+        let right = g.source(vec_source(Vec::<KV<String, String>>::new()));
+        let join = g.full_outer_join(&left, &right);
+
+        let left_frames: Frames = Default::default();
+        let right_frames: Frames = Default::default();
+        let output: Frames = Default::default();
+
+        let kv = |k: &str, v: &str| KV(k.to_owned(), v.to_owned());
+        push(&left_frames, vec![kv("a", "l1"), kv("b", "l2")]);
+        push(&right_frames, vec![kv("a", "r1"), kv("c", "r2")]);
+
+        let inputs = [(LEFT_INPUT, &left_frames), (RIGHT_INPUT, &right_frames)];
+        tick(&mut g, join.id(), &inputs, &output, 10);
+
+        let frames: Vec<_> = drain::<KV<String, (Option<String>, Option<String>)>>(&output)
+            .into_iter()
+            .map(|frame| match frame {
+                Frame::Data(KV(k, (l, r))) => format!("{} {:?} {:?}", k, l, r),
+                other => format!("{:?}", other),
+            })
+            .collect();
+
+        assert_eq!(
+            frames,
+            vec![
+                r#"a Some("l1") Some("r1")"#,
+                r#"b Some("l2") None"#,
+                r#"c None Some("r2")"#,
+                "End",
+            ]
+        );
+    }
+
+    #[test]
+    fn spilled_join_matches_in_memory_join() {
+        let spill_dir = std::env::temp_dir().join(format!("mixlayer-join-{}", std::process::id()));
+
+        let mut g = MxlGraph::new();
+        let left = g.source(vec_source(Vec::<KV<String, u32>>::new()));
+        let right = g.source(vec_source(Vec::<KV<String, u32>>::new()));
+
+        let config = JoinConfig::new()
+            .build_side(JoinSide::Left)
+            .spill(crate::LocalFsSpill::new(&spill_dir).unwrap(), 1)
+            .partitions(3);
+        let join = g.join_with(&left, &right, Inner, config);
+
+        let left_frames: Frames = Default::default();
+        let right_frames: Frames = Default::default();
+        let output: Frames = Default::default();
+
+        let kv = |k: &str, v: u32| KV(k.to_owned(), v);
+        push(&left_frames, vec![kv("a", 1), kv("b", 2), kv("c", 3), kv("a", 4)]);
+        push(&right_frames, vec![kv("a", 10), kv("c", 30), kv("d", 40)]);
+
+        let inputs = [(LEFT_INPUT, &left_frames), (RIGHT_INPUT, &right_frames)];
+        tick(&mut g, join.id(), &inputs, &output, 20);
+
+        let mut pairs: Vec<_> = data(drain::<KV<String, (u32, u32)>>(&output))
+            .into_iter()
+            .map(|KV(k, (l, r))| format!("{} {} {}", k, l, r))
+            .collect();
+        pairs.sort();
+
+        assert_eq!(pairs, vec!["a 1 10", "a 4 10", "c 3 30"]);
+        assert!(g.is_finished(&join.id()));
+
+        std::fs::remove_dir_all(spill_dir).unwrap();
+    }
+
+    /// Joins `left` with `right` holding `build_side` in memory, returns the sorted output
+    fn run_join<M: JoinMode<u32, u32>>(
+        mode: M,
+        build_side: JoinSide,
+        left: &[(&str, u32)],
+        right: &[(&str, u32)],
+    ) -> Vec<String> {
+        let mut g = MxlGraph::new();
+        let left_source = g.source(vec_source(Vec::<KV<String, u32>>::new()));
+        let right_source = g.source(vec_source(Vec::<KV<String, u32>>::new()));
+        let join = g.join_with(&left_source, &right_source, mode, JoinConfig::new().build_side(build_side));
+
+        let left_frames: Frames = Default::default();
+        let right_frames: Frames = Default::default();
+        let output: Frames = Default::default();
+
+        let kvs = |kvs: &[(&str, u32)]| kvs.iter().map(|(k, v)| KV(k.to_string(), *v)).collect();
+        push::<KV<String, u32>>(&left_frames, kvs(left));
+        push::<KV<String, u32>>(&right_frames, kvs(right));
+
+        let inputs = [(LEFT_INPUT, &left_frames), (RIGHT_INPUT, &right_frames)];
+        tick(&mut g, join.id(), &inputs, &output, 20);
+
+        assert!(g.is_finished(&join.id()));
+
+        let mut out: Vec<_> = data(drain::<KV<String, M::Output>>(&output))
+            .into_iter()
+            .map(|KV(k, v)| format!("{} {:?}", k, v))
+            .collect();
+        out.sort();
+        out
+    }
+
+    const LEFT: &[(&str, u32)] = &[("a", 1), ("b", 2), ("a", 3)];
+    const RIGHT: &[(&str, u32)] = &[("a", 10), ("a", 11), ("c", 12)];
+
+    #[test]
+    fn right_join_emits_every_right_element() {
+        for build_side in [JoinSide::Left, JoinSide::Right] {
+            assert_eq!(
+                run_join(Right, build_side, LEFT, RIGHT),
+                vec![
+                    "a (Some(1), 10)",
+                    "a (Some(1), 11)",
+                    "a (Some(3), 10)",
+                    "a (Some(3), 11)",
+                    "c (None, 12)",
+                ],
+                "building on {:?}",
+                build_side
+            );
+        }
+    }
+
+    #[test]
+    fn semi_join_emits_matched_left_elements_once() {
+        for build_side in [JoinSide::Left, JoinSide::Right] {
+            assert_eq!(
+                run_join(Semi, build_side, LEFT, RIGHT),
+                vec!["a 1", "a 3"],
+                "building on {:?}",
+                build_side
+            );
+        }
+    }
+
+    #[test]
+    fn anti_join_emits_unmatched_left_elements() {
+        for build_side in [JoinSide::Left, JoinSide::Right] {
+            assert_eq!(
+                run_join(Anti, build_side, LEFT, RIGHT),
+                vec!["b 2"],
+                "building on {:?}",
+                build_side
+            );
+        }
+    }
+}
@@ -0,0 +1,374 @@
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use bytes::Bytes;
+
+use super::{JoinMode, VJoin, LEFT_INPUT, RIGHT_INPUT};
+use crate::graph::{MxlNode, MxlNodeCtx};
+use crate::{Frame, MxlData, Result, KV};
+
+/// How a symmetric join drops state so it can run over unbounded inputs. Elements that never
+/// matched are emitted when they're evicted, for modes that want them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Eviction {
+    /// Keeps every element until both inputs end
+    #[default]
+    Never,
+    /// Keeps at most this many elements per side, evicting the oldest first
+    Count(usize),
+    /// Evicts elements once they've been held this long, according to the graph's clock. The
+    /// clock counts whole seconds, so the TTL is rounded up to the next second.
+    Ttl(Duration),
+}
+
+struct Entry<V> {
+    value: V,
+    matched: bool,
+}
+
+/// Elements of one side of a symmetric join, keyed for lookup and kept in arrival order so
+/// the oldest can be evicted
+struct SymmetricTable<K, V> {
+    entries: HashMap<K, VecDeque<Entry<V>>>,
+    /// key and arrival time of every element, oldest first
+    order: VecDeque<(K, u64)>,
+}
+
+impl<K: MxlData + Eq + Hash, V: MxlData> SymmetricTable<K, V> {
+    fn new() -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    fn insert(&mut self, key: K, value: V, matched: bool, now: u64) {
+        self.entries
+            .entry(key.clone())
+            .or_default()
+            .push_back(Entry { value, matched });
+        self.order.push_back((key, now));
+    }
+
+    fn matches(&mut self, key: &K) -> impl Iterator<Item = &mut Entry<V>> {
+        self.entries.get_mut(key).into_iter().flatten()
+    }
+
+    /// Removes the oldest element. Elements of a key are evicted in the order they arrived, so
+    /// it's always the front of its key's queue.
+    fn pop_oldest(&mut self) -> Option<(K, Entry<V>)> {
+        let (key, _) = self.order.pop_front()?;
+        let values = self.entries.get_mut(&key)?;
+        let entry = values.pop_front()?;
+
+        if values.is_empty() {
+            self.entries.remove(&key);
+        }
+
+        Some((key, entry))
+    }
+
+    fn evict(&mut self, eviction: Eviction, now: u64) -> Vec<(K, Entry<V>)> {
+        let mut evicted = Vec::new();
+
+        match eviction {
+            Eviction::Never => (),
+            Eviction::Count(max) => {
+                while self.order.len() > max {
+                    evicted.extend(self.pop_oldest());
+                }
+            }
+            Eviction::Ttl(ttl) => {
+                while let Some((_, inserted_at)) = self.order.front() {
+                    if inserted_at.saturating_add(ttl.as_secs()) > now {
+                        break;
+                    }
+
+                    evicted.extend(self.pop_oldest());
+                }
+            }
+        }
+
+        evicted
+    }
+
+    fn drain(&mut self) -> Vec<(K, Entry<V>)> {
+        let mut drained = Vec::with_capacity(self.order.len());
+
+        while let Some(kv) = self.pop_oldest() {
+            drained.push(kv);
+        }
+
+        drained
+    }
+}
+
+/// Joins two keyed inputs without waiting for either to end. Both sides are indexed as they
+/// arrive and every element is matched against what the other side has seen so far, so matches
+/// are emitted as soon as both halves are present. State is bounded by `Eviction`.
+pub struct SymmetricHashJoin<K: MxlData, L: MxlData, R: MxlData, M: JoinMode<L, R>> {
+    _mode: PhantomData<M>,
+    eviction: Eviction,
+    left_table: SymmetricTable<K, L>,
+    right_table: SymmetricTable<K, R>,
+}
+
+impl<K, L, R, M> VJoin for SymmetricHashJoin<K, L, R, M>
+where
+    K: MxlData + Eq + Hash,
+    L: MxlData,
+    R: MxlData,
+    M: JoinMode<L, R>,
+{
+    type K = K;
+    type LV = L;
+    type RV = R;
+    type Output = KV<Self::K, M::Output>;
+}
+
+impl<K, L, R, M> MxlNode for SymmetricHashJoin<K, L, R, M>
+where
+    K: MxlData + Eq + Hash,
+    L: MxlData,
+    R: MxlData,
+    M: JoinMode<L, R>,
+{
+    fn tick(&mut self, ctx: &mut MxlNodeCtx) -> Result<()> {
+        let now = ctx.now();
+        let mut out = Vec::new();
+
+        // one frame from each side per tick so neither input can starve the other
+        for port in [LEFT_INPUT, RIGHT_INPUT] {
+            match ctx.recv(port) {
+                Some(Frame::Data(data)) if port == LEFT_INPUT => {
+                    self.insert_left(data, now, &mut out)
+                }
+                Some(Frame::Data(data)) => self.insert_right(data, now, &mut out),
+                Some(Frame::Error) => out.push(Frame::Error),
+                Some(Frame::End) | None => (),
+            }
+        }
+
+        self.evict(now, &mut out);
+
+        for frame in out {
+            self.send(ctx, frame)?;
+        }
+
+        Ok(())
+    }
+
+    fn on_finish(&mut self, ctx: &mut MxlNodeCtx) -> Result<()> {
+        let mut out = Vec::new();
+
+        let left = self.left_table.drain();
+        let right = self.right_table.drain();
+        Self::unmatched(left, right, &mut out);
+
+        for frame in out {
+            self.send(ctx, frame)?;
+        }
+
+        Ok(())
+    }
+
+    fn default_label(&self) -> Option<String> {
+        Some(format!("Symmetric{:?}Join", M::KIND))
+    }
+}
+
+impl<K, L, R, M> Default for SymmetricHashJoin<K, L, R, M>
+where
+    K: MxlData + Eq + Hash,
+    L: MxlData,
+    R: MxlData,
+    M: JoinMode<L, R>,
+{
+    fn default() -> Self {
+        Self::new(Eviction::Never)
+    }
+}
+
+impl<K, L, R, M> SymmetricHashJoin<K, L, R, M>
+where
+    K: MxlData + Eq + Hash,
+    L: MxlData,
+    R: MxlData,
+    M: JoinMode<L, R>,
+{
+    pub fn new(eviction: Eviction) -> Self {
+        // a sub-second TTL would truncate to 0 and evict everything on the next tick
+        let eviction = match eviction {
+            Eviction::Ttl(ttl) if ttl.subsec_nanos() > 0 => {
+                Eviction::Ttl(Duration::from_secs(ttl.as_secs() + 1))
+            }
+            other => other,
+        };
+
+        Self {
+            _mode: Default::default(),
+            eviction,
+            left_table: SymmetricTable::new(),
+            right_table: SymmetricTable::new(),
+        }
+    }
+
+    fn insert_left(&mut self, data: Bytes, now: u64, out: &mut Vec<Frame<KV<K, M::Output>>>) {
+        let (key, left) = match KV::<K, L>::from_buffer_frame(Frame::Data(data)) {
+            Frame::Data(kv) => kv.into_parts(),
+            _ => return,
+        };
+
+        let mut matched = false;
+
+        for right in self.right_table.matches(&key) {
+            right.matched = true;
+            matched = true;
+
+            if let Some(o) = M::matched(&left, &right.value) {
+                out.push(Frame::Data(KV(key.clone(), o)));
+            }
+        }
+
+        if matched {
+            if let Some(o) = M::matched_left(&left) {
+                out.push(Frame::Data(KV(key.clone(), o)));
+            }
+        }
+
+        self.left_table.insert(key, left, matched, now);
+    }
+
+    fn insert_right(&mut self, data: Bytes, now: u64, out: &mut Vec<Frame<KV<K, M::Output>>>) {
+        let (key, right) = match KV::<K, R>::from_buffer_frame(Frame::Data(data)) {
+            Frame::Data(kv) => kv.into_parts(),
+            _ => return,
+        };
+
+        let mut matched = false;
+
+        for left in self.left_table.matches(&key) {
+            matched = true;
+
+            if let Some(o) = M::matched(&left.value, &right) {
+                out.push(Frame::Data(KV(key.clone(), o)));
+            }
+
+            if !left.matched {
+                left.matched = true;
+
+                if let Some(o) = M::matched_left(&left.value) {
+                    out.push(Frame::Data(KV(key.clone(), o)));
+                }
+            }
+        }
+
+        self.right_table.insert(key, right, matched, now);
+    }
+
+    fn evict(&mut self, now: u64, out: &mut Vec<Frame<KV<K, M::Output>>>) {
+        let left = self.left_table.evict(self.eviction, now);
+        let right = self.right_table.evict(self.eviction, now);
+
+        Self::unmatched(left, right, out);
+    }
+
+    /// Emits elements leaving the join that nothing on the other side matched
+    fn unmatched(
+        left: Vec<(K, Entry<L>)>,
+        right: Vec<(K, Entry<R>)>,
+        out: &mut Vec<Frame<KV<K, M::Output>>>,
+    ) {
+        for (key, entry) in left.into_iter().filter(|(_, e)| !e.matched) {
+            if let Some(o) = M::unmatched_left(&entry.value) {
+                out.push(Frame::Data(KV(key, o)));
+            }
+        }
+
+        for (key, entry) in right.into_iter().filter(|(_, e)| !e.matched) {
+            if let Some(o) = M::unmatched_right(&entry.value) {
+                out.push(Frame::Data(KV(key, o)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::join::Left;
+    use crate::source::vec_source;
+    use crate::test_util::{drain, push_open, tick, Frames, ManualClock};
+    use crate::MxlGraph;
+
+    fn format_left_join(output: &Frames) -> Vec<String> {
+        drain::<KV<String, (String, Option<String>)>>(output)
+            .into_iter()
+            .map(|frame| match frame {
+                Frame::Data(KV(k, (l, r))) => format!("{} {} {:?}", k, l, r),
+                other => format!("{:?}", other),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn symmetric_join_emits_before_inputs_end() {
+        let mut g = MxlGraph::new();
+        let left = g.source(vec_source(Vec::<KV<String, String>>::new()));
+        let right = g.source(vec_source(Vec::<KV<String, String>>::new()));
+        let join = g.symmetric_join(&left, &right, Left, Eviction::Count(1));
+
+        let left_frames: Frames = Default::default();
+        let right_frames: Frames = Default::default();
+        let output: Frames = Default::default();
+
+        // neither input ends
+        let kv = |k: &str, v: &str| KV(k.to_owned(), v.to_owned());
+        push_open(&left_frames, vec![kv("a", "l1"), kv("b", "l2"), kv("c", "l3")]);
+        push_open(&right_frames, vec![kv("a", "r1")]);
+
+        let inputs = [(LEFT_INPUT, &left_frames), (RIGHT_INPUT, &right_frames)];
+        tick(&mut g, join.id(), &inputs, &output, 5);
+
+        assert!(!g.is_finished(&join.id()));
+
+        // b is evicted unmatched once c arrives
+        assert_eq!(format_left_join(&output), vec!["a l1 Some(\"r1\")", "b l2 None"]);
+    }
+
+    #[test]
+    fn sub_second_ttl_keeps_elements_until_the_next_second() {
+        let now = Arc::new(AtomicU64::new(100));
+
+        let mut g = MxlGraph::new();
+        g.set_clock(ManualClock(now.clone()));
+
+        let left = g.source(vec_source(Vec::<KV<String, String>>::new()));
+        let right = g.source(vec_source(Vec::<KV<String, String>>::new()));
+        let join = g.symmetric_join(&left, &right, Left, Eviction::Ttl(Duration::from_millis(500)));
+
+        let left_frames: Frames = Default::default();
+        let right_frames: Frames = Default::default();
+        let output: Frames = Default::default();
+
+        let kv = |k: &str, v: &str| KV(k.to_owned(), v.to_owned());
+        let inputs = [(LEFT_INPUT, &left_frames), (RIGHT_INPUT, &right_frames)];
+
+        push_open(&left_frames, vec![kv("a", "l1"), kv("b", "l2")]);
+        tick(&mut g, join.id(), &inputs, &output, 1);
+
+        // a is still held a tick later in the same second
+        push_open(&right_frames, vec![kv("a", "r1")]);
+        tick(&mut g, join.id(), &inputs, &output, 1);
+
+        now.store(101, Ordering::Relaxed);
+        tick(&mut g, join.id(), &inputs, &output, 1);
+
+        assert_eq!(format_left_join(&output), vec!["a l1 Some(\"r1\")", "b l2 None"]);
+    }
+}
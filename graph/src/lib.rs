@@ -1,17 +1,25 @@
 // mod channel;
+mod clock;
 mod graph;
-mod join;
+mod operator;
+mod spill;
+#[cfg(test)]
+mod test_util;
 
 //TODO eventually take these private, but public for now to suppress unused warnings
+pub mod join;
 pub mod sink;
 pub mod source;
 pub mod transform;
 
-pub use graph::{Input, Output, MxlEdge, MxlGraph, MxlNode, MxlNodeCtx, MxlNodeId, MxlNodeRef, MxlNodeType};
-pub use join::MxlLeftJoin;
+pub use clock::{Clock, SystemClock};
+pub use graph::{Input, Output, MxlEdge, MxlGraph, MxlNode, MxlNodeCtx, MxlNodeId, MxlNodeRef, MxlNodeType, MxlJoinRef};
+pub use join::{Eviction, JoinConfig, JoinKind, JoinMode, JoinSide, MxlJoin, SymmetricHashJoin};
+pub use operator::{operator, Emitter, OperatorNode, StreamOperator};
 pub use sink::MxlSink;
 pub use source::MxlSource;
-pub use transform::MxlTransform;
+pub use spill::{read_frame, write_frame, LocalFsSpill, SpillStore};
+pub use transform::{LateData, MxlTransform, SortConfig, Window};
 pub use mixlayer_data::{Frame, MxlData, KV};
 pub use mixlayer_data::{InputChannel, OutputChannel};
 
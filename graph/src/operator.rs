@@ -0,0 +1,262 @@
+use std::collections::HashSet;
+
+use log::warn;
+
+use crate::graph::{MxlNode, MxlNodeCtx};
+use crate::{Frame, MxlData, MxlSink, MxlTransform, Result};
+
+/// Push-style alternative to implementing `MxlNode::tick` by hand. The operator is called back
+/// for every element and when its inputs end, and `OperatorNode` takes care of reading frames,
+/// propagating `End` exactly once and ignoring input after the node has finished.
+pub trait StreamOperator {
+    type Input: MxlData;
+    type Output: MxlData;
+
+    /// Called for every element received on any input port
+    fn on_element(&mut self, element: Self::Input, out: &mut Emitter<Self::Output>) -> Result<()>;
+
+    /// Called once when every upstream channel connected to `port` has sent `End`. After the
+    /// last port ends the node sends `End` downstream, so this is the place to flush state.
+    fn on_end_of_input(&mut self, _port: u32, _out: &mut Emitter<Self::Output>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called when an upstream node sends `Frame::Error`, forwards the error by default
+    fn on_error(&mut self, _port: u32, out: &mut Emitter<Self::Output>) -> Result<()> {
+        out.error();
+        Ok(())
+    }
+
+    fn label(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Collects the frames a `StreamOperator` produces during a callback
+pub struct Emitter<O> {
+    frames: Vec<Frame<O>>,
+}
+
+impl<O: MxlData> Emitter<O> {
+    pub(crate) fn new() -> Self {
+        Self { frames: Vec::new() }
+    }
+
+    pub fn emit(&mut self, element: O) {
+        self.frames.push(Frame::Data(element));
+    }
+
+    pub fn emit_all<I: IntoIterator<Item = O>>(&mut self, elements: I) {
+        self.frames.extend(elements.into_iter().map(Frame::Data));
+    }
+
+    /// Sends a `Frame::Error` downstream
+    pub fn error(&mut self) {
+        self.frames.push(Frame::Error);
+    }
+
+    pub(crate) fn drain(&mut self) -> impl Iterator<Item = Frame<O>> + '_ {
+        self.frames.drain(..)
+    }
+}
+
+/// Adapts a `StreamOperator` to `MxlNode` so it can be added to a graph as a transform, or as a
+/// sink if its output is `()`
+pub struct OperatorNode<Op: StreamOperator> {
+    op: Op,
+    /// input ports `on_end_of_input` has been called for
+    ended_ports: HashSet<u32>,
+    finished: bool,
+}
+
+impl<Op: StreamOperator> OperatorNode<Op> {
+    pub fn new(op: Op) -> Self {
+        Self {
+            op,
+            ended_ports: HashSet::new(),
+            finished: false,
+        }
+    }
+
+    pub fn operator(&self) -> &Op {
+        &self.op
+    }
+
+    pub fn into_operator(self) -> Op {
+        self.op
+    }
+
+    fn recv_port(
+        &mut self,
+        ctx: &mut MxlNodeCtx,
+        port: u32,
+        out: &mut Emitter<Op::Output>,
+    ) -> Result<()> {
+        match ctx.recv(port) {
+            Some(Frame::Data(data)) => match Op::Input::from_buffer_frame(Frame::Data(data)) {
+                Frame::Data(element) => self.op.on_element(element, out)?,
+                // a frame that doesn't decode is treated like an upstream error
+                _ => {
+                    warn!("couldn't decode frame on input {}", port);
+                    self.op.on_error(port, out)?
+                }
+            },
+            Some(Frame::Error) => self.op.on_error(port, out)?,
+            Some(Frame::End) if ctx.input_ended(port) => {
+                self.ended_ports.insert(port);
+                self.op.on_end_of_input(port, out)?;
+            }
+            Some(Frame::End) | None => (),
+        }
+
+        Ok(())
+    }
+
+    fn flush(&self, ctx: &mut MxlNodeCtx, out: &mut Emitter<Op::Output>) -> Result<()> {
+        // sinks have no output port to send to
+        if !ctx.has_output(0) {
+            out.frames.clear();
+            return Ok(());
+        }
+
+        for frame in out.drain() {
+            let frame = match frame {
+                Frame::Data(d) => d
+                    .into_buffer_frame()
+                    .map_err(|_| anyhow::anyhow!("error serializing frame"))?,
+                Frame::End => Frame::End,
+                Frame::Error => Frame::Error,
+            };
+
+            ctx.send(0, frame);
+        }
+
+        Ok(())
+    }
+}
+
+impl<Op: StreamOperator> MxlNode for OperatorNode<Op> {
+    fn tick(&mut self, ctx: &mut MxlNodeCtx) -> Result<()> {
+        if self.finished {
+            return Ok(());
+        }
+
+        let mut out = Emitter::new();
+
+        let mut ports: Vec<u32> = ctx.inputs.keys().copied().collect();
+        ports.sort();
+
+        // read at most one frame per port so a busy input can't starve the others
+        let mut result = Ok(());
+        for port in ports.iter().copied() {
+            if self.ended_ports.contains(&port) {
+                continue;
+            }
+
+            result = self.recv_port(ctx, port, &mut out);
+
+            if result.is_err() {
+                break;
+            }
+        }
+
+        // anything emitted before an error still goes downstream
+        self.flush(ctx, &mut out)?;
+        result?;
+
+        if !ports.is_empty() && ports.iter().all(|p| self.ended_ports.contains(p)) {
+            self.finished = true;
+
+            if ctx.has_output(0) {
+                ctx.send(0, Frame::End);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn default_label(&self) -> Option<String> {
+        self.op.label()
+    }
+}
+
+impl<Op: StreamOperator> MxlTransform for OperatorNode<Op> {
+    type Input = Op::Input;
+    type Output = Op::Output;
+}
+
+impl<Op: StreamOperator<Output = ()>> MxlSink for OperatorNode<Op> {
+    type Input = Op::Input;
+}
+
+impl<Op: StreamOperator> From<Op> for OperatorNode<Op> {
+    fn from(op: Op) -> Self {
+        OperatorNode::new(op)
+    }
+}
+
+pub fn operator<Op: StreamOperator>(op: Op) -> OperatorNode<Op> {
+    OperatorNode::new(op)
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::*;
+    use crate::test_util::{drain, tick, Frames};
+    use crate::MxlGraph;
+
+    /// A byte, frames of any other length don't decode
+    #[derive(Debug, Clone)]
+    struct Byte(u8);
+
+    impl MxlData for Byte {
+        fn from_buffer_frame(frame: Frame<Bytes>) -> Frame<Self> {
+            match frame {
+                Frame::Data(d) if d.len() == 1 => Frame::Data(Byte(d[0])),
+                Frame::Data(_) | Frame::Error => Frame::Error,
+                Frame::End => Frame::End,
+            }
+        }
+
+        fn into_buffer_frame(self) -> std::result::Result<Frame<Bytes>, ()> {
+            Ok(Frame::Data(Bytes::from(vec![self.0])))
+        }
+    }
+
+    struct Forward;
+
+    impl StreamOperator for Forward {
+        type Input = Byte;
+        type Output = Byte;
+
+        fn on_element(&mut self, element: Byte, out: &mut Emitter<Byte>) -> Result<()> {
+            out.emit(element);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn undecodable_frame_is_routed_to_on_error() {
+        let mut g = MxlGraph::new();
+        let node = g.transform(OperatorNode::new(Forward));
+
+        let input: Frames = Default::default();
+        let output: Frames = Default::default();
+
+        input.borrow_mut().extend([
+            Frame::Data(Bytes::from_static(&[1])),
+            Frame::Data(Bytes::from_static(&[2, 3])),
+            Frame::Data(Bytes::from_static(&[4])),
+        ]);
+        tick(&mut g, node.id(), &[(0, &input)], &output, 3);
+
+        let frames: Vec<_> = drain::<Byte>(&output)
+            .into_iter()
+            .map(|frame| format!("{:?}", frame))
+            .collect();
+
+        assert_eq!(frames, vec!["Data(Byte(1))", "Error", "Data(Byte(4))"]);
+    }
+}
@@ -0,0 +1,99 @@
+use std::fs;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::{anyhow, Context};
+use bytes::Bytes;
+
+use crate::{Frame, Result};
+
+/// Storage for operator state that doesn't fit in memory, e.g. the partitions of a join that
+/// spilled. State is written as named runs which are read back in the order they were written.
+pub trait SpillStore: Send {
+    /// Creates a run, replacing any existing run with the same name
+    fn create(&mut self, name: &str) -> Result<Box<dyn Write + Send>>;
+
+    /// Opens a previously written run for reading
+    fn open(&mut self, name: &str) -> Result<Box<dyn Read + Send>>;
+
+    /// Removes a run once it's no longer needed
+    fn remove(&mut self, name: &str) -> Result<()>;
+}
+
+/// Spills runs to files in a directory on the local filesystem
+pub struct LocalFsSpill {
+    dir: PathBuf,
+}
+
+impl LocalFsSpill {
+    pub fn new<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let dir = dir.as_ref().to_owned();
+
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("error creating spill directory {}", dir.display()))?;
+
+        Ok(Self { dir })
+    }
+}
+
+impl SpillStore for LocalFsSpill {
+    fn create(&mut self, name: &str) -> Result<Box<dyn Write + Send>> {
+        let file = fs::File::create(self.dir.join(name))?;
+        Ok(Box::new(BufWriter::new(file)))
+    }
+
+    fn open(&mut self, name: &str) -> Result<Box<dyn Read + Send>> {
+        let file = fs::File::open(self.dir.join(name))?;
+        Ok(Box::new(BufReader::new(file)))
+    }
+
+    fn remove(&mut self, name: &str) -> Result<()> {
+        match fs::remove_file(self.dir.join(name)) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+static NEXT_SPILL_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Prefix for the names of an operator's runs, unique within the process so operators can
+/// share a store, e.g. `join-3`
+pub(crate) fn run_prefix(operator: &str) -> String {
+    format!(
+        "{}-{}",
+        operator,
+        NEXT_SPILL_ID.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Writes a frame to a run in the same encoding used on edges
+pub fn write_frame(w: &mut dyn Write, frame: Frame<Bytes>) -> Result<()> {
+    w.write_all(&frame.into_bytes())?;
+    Ok(())
+}
+
+/// Reads the next frame written by `write_frame`, `None` at the end of the run
+pub fn read_frame(r: &mut dyn Read) -> Result<Option<Frame<Bytes>>> {
+    let mut ordinal = [0u8; 1];
+
+    if r.read(&mut ordinal)? == 0 {
+        return Ok(None);
+    }
+
+    match ordinal[0] {
+        0 => {
+            let mut len = [0u8; 4];
+            r.read_exact(&mut len)?;
+
+            let mut data = vec![0u8; u32::from_be_bytes(len) as usize];
+            r.read_exact(&mut data)?;
+
+            Ok(Some(Frame::Data(data.into())))
+        }
+        1 => Ok(Some(Frame::End)),
+        2 => Ok(Some(Frame::Error)),
+        other => Err(anyhow!("invalid frame ordinal {} in spilled run", other)),
+    }
+}
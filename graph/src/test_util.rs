@@ -0,0 +1,109 @@
+//! Fixtures for driving single nodes of a graph against in-memory channels in unit tests
+
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use bytes::Bytes;
+
+use crate::{
+    Clock, Frame, Input, InputChannel, MxlData, MxlGraph, MxlNodeCtx, MxlNodeId, Output, OutputChannel,
+};
+
+pub(crate) type Frames = Rc<RefCell<VecDeque<Frame<Bytes>>>>;
+
+/// An edge backed by a shared queue, reading from it pops the frames written to it
+pub(crate) struct VecChannel(pub Frames);
+
+impl InputChannel for VecChannel {
+    fn finished(&self) -> bool {
+        self.0.borrow().is_empty()
+    }
+
+    fn finished_writing(&self) -> bool {
+        true
+    }
+
+    fn recv(&self) -> Option<Frame<Bytes>> {
+        self.0.borrow_mut().pop_front()
+    }
+}
+
+impl OutputChannel for VecChannel {
+    fn send(&self, data: Frame<Bytes>) {
+        self.0.borrow_mut().push_back(data)
+    }
+}
+
+/// A clock tests move by hand, in seconds like `SystemClock`
+pub(crate) struct ManualClock(pub Arc<AtomicU64>);
+
+impl Clock for ManualClock {
+    fn now(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Queues `data` followed by End
+pub(crate) fn push<T: MxlData>(frames: &Frames, data: Vec<T>) {
+    push_open(frames, data);
+    frames.borrow_mut().push_back(Frame::End);
+}
+
+/// Queues `data` without ending the input
+pub(crate) fn push_open<T: MxlData>(frames: &Frames, data: Vec<T>) {
+    let mut frames = frames.borrow_mut();
+
+    for d in data {
+        frames.push_back(d.into_buffer_frame().unwrap());
+    }
+}
+
+/// Builds a ctx reading from `(port, channel)` pairs and writing to `output`
+pub(crate) fn ctx(inputs: &[(u32, &Frames)], output: &Frames) -> MxlNodeCtx {
+    let mut input_chs: HashMap<u32, Vec<Box<dyn InputChannel>>> = HashMap::new();
+
+    for (port, frames) in inputs {
+        let ch = Box::new(VecChannel((*frames).clone()));
+        input_chs.entry(*port).or_default().push(ch);
+    }
+
+    let mut ctx = MxlNodeCtx::new();
+    ctx.inputs = input_chs
+        .into_iter()
+        .map(|(port, chs)| (port, Input::new(chs)))
+        .collect();
+    ctx.outputs = HashMap::from([(
+        0,
+        Output {
+            output_chs: vec![Box::new(VecChannel(output.clone()))],
+        },
+    )]);
+    ctx
+}
+
+/// Ticks `node` `ticks` times through the graph, so run state is tracked as it is in an app
+pub(crate) fn tick(g: &mut MxlGraph, node: MxlNodeId, inputs: &[(u32, &Frames)], output: &Frames, ticks: usize) {
+    for _ in 0..ticks {
+        g.tick_node(&node, ctx(inputs, output)).unwrap();
+    }
+}
+
+/// Removes and decodes the frames sent to `output`
+pub(crate) fn drain<T: MxlData>(output: &Frames) -> Vec<Frame<T>> {
+    let frames: Vec<_> = output.borrow_mut().drain(..).collect();
+    frames.into_iter().map(T::from_buffer_frame).collect()
+}
+
+/// The elements of data frames
+pub(crate) fn data<T>(frames: Vec<Frame<T>>) -> Vec<T> {
+    frames
+        .into_iter()
+        .filter_map(|frame| match frame {
+            Frame::Data(d) => Some(d),
+            _ => None,
+        })
+        .collect()
+}
@@ -0,0 +1,427 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::AddAssign;
+
+use crate::{
+    graph::{MxlNode, MxlNodeCtx},
+    Frame, MxlData, Result, KV,
+};
+
+use super::MxlTransform;
+
+/// Folds the values of a key into an accumulator one at a time, so only one accumulator per
+/// key is held instead of every value
+pub trait Aggregator<V>: Send + Sync + 'static {
+    type Acc: Send + Sync;
+    type Output: MxlData;
+
+    /// Creates the accumulator for a key from its first value
+    fn create(&self, value: V) -> Self::Acc;
+
+    /// Adds another value of the key to its accumulator
+    fn add(&self, acc: &mut Self::Acc, value: V);
+
+    /// Turns an accumulator into the value emitted for its key
+    fn finish(&self, acc: Self::Acc) -> Self::Output;
+}
+
+/// Aggregates the values of every key, emitting one `KV` per key once the input ends
+pub struct AggregateByKey<K, V, A>
+where
+    K: MxlData + Eq + Hash,
+    V: MxlData,
+    A: Aggregator<V>,
+{
+    aggregator: A,
+    state: HashMap<K, A::Acc>,
+    /// emit and clear the state once it holds more keys than this, for partial aggregates
+    /// that are merged downstream
+    max_keys: Option<usize>,
+    label: &'static str,
+    _v: PhantomData<V>,
+}
+
+impl<K, V, A> AggregateByKey<K, V, A>
+where
+    K: MxlData + Eq + Hash,
+    V: MxlData,
+    A: Aggregator<V>,
+{
+    pub fn new(aggregator: A) -> Self {
+        Self {
+            aggregator,
+            state: HashMap::new(),
+            max_keys: None,
+            label: "AggregateByKey",
+            _v: Default::default(),
+        }
+    }
+
+    pub(crate) fn partial(mut self, max_keys: usize) -> Self {
+        self.max_keys = Some(max_keys);
+        self
+    }
+
+    pub(crate) fn with_label(mut self, label: &'static str) -> Self {
+        self.label = label;
+        self
+    }
+
+    fn flush(&mut self, ctx: &mut MxlNodeCtx) -> Result<()> {
+        for (k, acc) in mem::take(&mut self.state) {
+            let output = self.aggregator.finish(acc);
+            self.send(ctx, Frame::Data(KV(k, output)))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<K, V, A> MxlTransform for AggregateByKey<K, V, A>
+where
+    K: MxlData + Eq + Hash,
+    V: MxlData,
+    A: Aggregator<V>,
+{
+    type Input = KV<K, V>;
+    type Output = KV<K, A::Output>;
+}
+
+impl<K, V, A> MxlNode for AggregateByKey<K, V, A>
+where
+    K: MxlData + Eq + Hash,
+    V: MxlData,
+    A: Aggregator<V>,
+{
+    fn tick(&mut self, ctx: &mut MxlNodeCtx) -> Result<()> {
+        if let Some(Frame::Data(data)) = self.recv(ctx) {
+            let (key, value) = data.into_parts();
+
+            match self.state.get_mut(&key) {
+                Some(acc) => self.aggregator.add(acc, value),
+                None => {
+                    let acc = self.aggregator.create(value);
+                    self.state.insert(key, acc);
+                }
+            }
+
+            if self
+                .max_keys
+                .map(|max| self.state.len() > max)
+                .unwrap_or(false)
+            {
+                self.flush(ctx)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn on_finish(&mut self, ctx: &mut MxlNodeCtx) -> Result<()> {
+        self.flush(ctx)
+    }
+
+    fn default_label(&self) -> Option<String> {
+        Some(self.label.to_owned())
+    }
+}
+
+/// Combines values with `f`, see `MxlNodeRef::reduce_by_key`
+pub struct Reduce<F>(pub F);
+
+impl<V, F> Aggregator<V> for Reduce<F>
+where
+    V: MxlData,
+    F: Fn(V, V) -> V + Send + Sync + 'static,
+{
+    type Acc = Option<V>;
+    type Output = V;
+
+    fn create(&self, value: V) -> Option<V> {
+        Some(value)
+    }
+
+    fn add(&self, acc: &mut Option<V>, value: V) {
+        *acc = match acc.take() {
+            Some(prev) => Some((self.0)(prev, value)),
+            None => Some(value),
+        };
+    }
+
+    fn finish(&self, acc: Option<V>) -> V {
+        acc.expect("reduce accumulator is created with a value")
+    }
+}
+
+/// Folds values into a copy of `init`, see `MxlNodeRef::fold_by_key`
+pub struct Fold<A, F> {
+    init: A,
+    f: F,
+}
+
+impl<A, F> Fold<A, F> {
+    pub fn new(init: A, f: F) -> Self {
+        Self { init, f }
+    }
+}
+
+impl<V, A, F> Aggregator<V> for Fold<A, F>
+where
+    V: MxlData,
+    A: MxlData,
+    F: Fn(A, V) -> A + Send + Sync + 'static,
+{
+    type Acc = Option<A>;
+    type Output = A;
+
+    fn create(&self, value: V) -> Option<A> {
+        Some((self.f)(self.init.clone(), value))
+    }
+
+    fn add(&self, acc: &mut Option<A>, value: V) {
+        let prev = acc.take().unwrap_or_else(|| self.init.clone());
+        *acc = Some((self.f)(prev, value));
+    }
+
+    fn finish(&self, acc: Option<A>) -> A {
+        acc.unwrap_or_else(|| self.init.clone())
+    }
+}
+
+/// Counts the values of each key
+pub struct Count;
+
+impl<V: MxlData> Aggregator<V> for Count {
+    type Acc = u64;
+    type Output = u64;
+
+    fn create(&self, _value: V) -> u64 {
+        1
+    }
+
+    fn add(&self, acc: &mut u64, _value: V) {
+        *acc += 1;
+    }
+
+    fn finish(&self, acc: u64) -> u64 {
+        acc
+    }
+}
+
+/// Adds up the values of each key
+pub struct Sum;
+
+impl<V: MxlData + AddAssign> Aggregator<V> for Sum {
+    type Acc = V;
+    type Output = V;
+
+    fn create(&self, value: V) -> V {
+        value
+    }
+
+    fn add(&self, acc: &mut V, value: V) {
+        *acc += value;
+    }
+
+    fn finish(&self, acc: V) -> V {
+        acc
+    }
+}
+
+/// Keeps the smallest value of each key
+pub struct Min;
+
+impl<V: MxlData + PartialOrd> Aggregator<V> for Min {
+    type Acc = V;
+    type Output = V;
+
+    fn create(&self, value: V) -> V {
+        value
+    }
+
+    fn add(&self, acc: &mut V, value: V) {
+        if value < *acc {
+            *acc = value;
+        }
+    }
+
+    fn finish(&self, acc: V) -> V {
+        acc
+    }
+}
+
+/// Keeps the largest value of each key
+pub struct Max;
+
+impl<V: MxlData + PartialOrd> Aggregator<V> for Max {
+    type Acc = V;
+    type Output = V;
+
+    fn create(&self, value: V) -> V {
+        value
+    }
+
+    fn add(&self, acc: &mut V, value: V) {
+        if value > *acc {
+            *acc = value;
+        }
+    }
+
+    fn finish(&self, acc: V) -> V {
+        acc
+    }
+}
+
+/// Numbers `Mean` can average. 64-bit integers past 2^53 lose precision.
+pub trait AsF64 {
+    fn as_f64(&self) -> f64;
+}
+
+macro_rules! impl_as_f64 {
+    ($($ty:ty),*) => {
+        $(
+            impl AsF64 for $ty {
+                fn as_f64(&self) -> f64 {
+                    *self as f64
+                }
+            }
+        )*
+    };
+}
+
+impl_as_f64!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize, f32, f64);
+
+/// Averages the values of each key
+pub struct Mean;
+
+impl<V: MxlData + AsF64> Aggregator<V> for Mean {
+    /// sum and count
+    type Acc = (f64, u64);
+    type Output = f64;
+
+    fn create(&self, value: V) -> (f64, u64) {
+        (value.as_f64(), 1)
+    }
+
+    fn add(&self, acc: &mut (f64, u64), value: V) {
+        acc.0 += value.as_f64();
+        acc.1 += 1;
+    }
+
+    fn finish(&self, acc: (f64, u64)) -> f64 {
+        acc.0 / acc.1 as f64
+    }
+}
+
+/// Counts the distinct values of each key, every distinct value is held until the input ends
+pub struct DistinctCount;
+
+impl<V: MxlData + Eq + Hash> Aggregator<V> for DistinctCount {
+    type Acc = HashSet<V>;
+    type Output = u64;
+
+    fn create(&self, value: V) -> HashSet<V> {
+        HashSet::from([value])
+    }
+
+    fn add(&self, acc: &mut HashSet<V>, value: V) {
+        acc.insert(value);
+    }
+
+    fn finish(&self, acc: HashSet<V>) -> u64 {
+        acc.len() as u64
+    }
+}
+
+/// The partial stage of `combine_by_key`, builds a combiner per key from its values
+pub struct CombineValues<C, Cr, Mv> {
+    create: Cr,
+    merge_value: Mv,
+    _c: PhantomData<C>,
+}
+
+impl<C, Cr, Mv> CombineValues<C, Cr, Mv> {
+    pub fn new(create: Cr, merge_value: Mv) -> Self {
+        Self {
+            create,
+            merge_value,
+            _c: Default::default(),
+        }
+    }
+}
+
+impl<V, C, Cr, Mv> Aggregator<V> for CombineValues<C, Cr, Mv>
+where
+    V: MxlData,
+    C: MxlData,
+    Cr: Fn(V) -> C + Send + Sync + 'static,
+    Mv: Fn(C, V) -> C + Send + Sync + 'static,
+{
+    type Acc = Option<C>;
+    type Output = C;
+
+    fn create(&self, value: V) -> Option<C> {
+        Some((self.create)(value))
+    }
+
+    fn add(&self, acc: &mut Option<C>, value: V) {
+        *acc = match acc.take() {
+            Some(c) => Some((self.merge_value)(c, value)),
+            None => Some((self.create)(value)),
+        };
+    }
+
+    fn finish(&self, acc: Option<C>) -> C {
+        acc.expect("combiner is created with a value")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{data, drain, push, tick, Frames};
+    use crate::{transform, MxlGraph};
+
+    #[test]
+    fn aggregate_by_key_keeps_one_accumulator_per_key() {
+        let mut g = MxlGraph::new();
+        let mean = g.transform(transform::aggregate_by_key::<String, u32, _>(Mean));
+
+        let input: Frames = Default::default();
+        let output: Frames = Default::default();
+
+        let kv = |k: &str, v: u32| KV(k.to_owned(), v);
+        push(&input, vec![kv("a", 1), kv("b", 4), kv("a", 2)]);
+        tick(&mut g, mean.id(), &[(0, &input)], &output, 5);
+
+        let mut means: Vec<_> = data(drain::<KV<String, f64>>(&output))
+            .into_iter()
+            .map(|KV(k, v)| format!("{} {}", k, v))
+            .collect();
+        means.sort();
+
+        assert_eq!(means, vec!["a 1.5", "b 4"]);
+    }
+
+    #[test]
+    fn mean_of_u64_counts() {
+        let mut g = MxlGraph::new();
+        let mean = g.transform(transform::aggregate_by_key::<String, u64, _>(Mean));
+
+        let input: Frames = Default::default();
+        let output: Frames = Default::default();
+
+        let kv = |k: &str, v: u64| KV(k.to_owned(), v);
+        push(&input, vec![kv("a", 3), kv("a", 4)]);
+        tick(&mut g, mean.id(), &[(0, &input)], &output, 5);
+
+        let means: Vec<_> = data(drain::<KV<String, f64>>(&output))
+            .into_iter()
+            .map(|KV(k, v)| (k, v))
+            .collect();
+
+        assert_eq!(means, vec![("a".to_owned(), 3.5)]);
+    }
+}
@@ -1,15 +1,27 @@
 use std::mem;
+use std::time::Duration;
+
+use anyhow::anyhow;
 
 use crate::{graph::MxlNode, Frame, Result, MxlData, MxlNodeCtx, MxlTransform};
 
 /// Transforms that accumulates inputs into a batch and
-/// then sends the batch to downstream nodes for processing
+/// then sends the batch to downstream nodes for processing.
+/// A batch is sent once it holds `max_items` elements, or, if set,
+/// once its encoded elements add up to `max_bytes` or its first
+/// element has waited `max_wait`, whichever comes first.
+/// Errors, and elements that fail to decode, are forwarded as `Frame::Error` as they arrive.
 pub struct BatchXform<I>
 where
     I: MxlData,
 {
     batch_size: usize,
+    max_bytes: Option<usize>,
+    max_wait: Option<Duration>,
     cur_batch: Vec<I>,
+    cur_bytes: usize,
+    /// time the first element of the current batch arrived
+    started_at: Option<u64>,
 }
 
 impl<I> BatchXform<I>
@@ -19,11 +31,36 @@ where
     pub fn new(batch_size: usize) -> Self {
         Self {
             batch_size,
+            max_bytes: None,
+            max_wait: None,
             cur_batch: Vec::new(),
+            cur_bytes: 0,
+            started_at: None,
         }
     }
 
+    /// Limits batches to `max_items` elements and `max_bytes` of encoded elements, and sends
+    /// a partial batch once it's been open for `max_wait`. Time comes from the graph's clock,
+    /// so `max_wait` must be at least a second.
+    pub fn with_limits(max_items: usize, max_bytes: usize, max_wait: Duration) -> Result<Self> {
+        if max_wait < Duration::from_secs(1) {
+            return Err(anyhow!(
+                "batch max_wait of {:?} is shorter than the clock's one-second resolution",
+                max_wait
+            ));
+        }
+
+        Ok(Self {
+            max_bytes: Some(max_bytes),
+            max_wait: Some(max_wait),
+            ..Self::new(max_items)
+        })
+    }
+
     fn send_batch(&mut self, ctx: &mut MxlNodeCtx) -> Result<()> {
+        self.cur_bytes = 0;
+        self.started_at = None;
+
         if !self.cur_batch.is_empty() {
             let batch_to_send =
                 mem::replace(&mut self.cur_batch, Vec::with_capacity(self.batch_size));
@@ -32,6 +69,21 @@ where
 
         Ok(())
     }
+
+    fn would_exceed_bytes(&self, size: usize) -> bool {
+        self.max_bytes
+            .map(|max| self.cur_bytes + size > max)
+            .unwrap_or(false)
+    }
+
+    fn waited_too_long(&self, now: u64) -> bool {
+        match (self.max_wait, self.started_at) {
+            (Some(max_wait), Some(started_at)) => {
+                Duration::from_secs(now.saturating_sub(started_at)) >= max_wait
+            }
+            _ => false,
+        }
+    }
 }
 
 impl<I> MxlNode for BatchXform<I>
@@ -39,24 +91,57 @@ where
     I: MxlData,
 {
     fn tick(&mut self, ctx: &mut MxlNodeCtx) -> Result<()> {
-        if ctx.recv_finished() {
-            self.send_batch(ctx)?;
-            self.send(ctx, Frame::End)?;
-        } else {
-            while let Some(next) = self.recv(ctx) {
-                if let Frame::Data(data) = next {
-                    self.cur_batch.push(data);
-                    if self.cur_batch.len() >= self.batch_size {
-                        self.send_batch(ctx)?;
-                        break; //send at most one batch per tick
+        let now = ctx.now();
+
+        // read the raw frame so the batch can be limited by encoded size
+        while let Some(next) = ctx.recv(0) {
+            if let Frame::Error = next {
+                // forwarded right away, like OperatorNode does, rather than held with the batch
+                self.send(ctx, Frame::Error)?;
+            }
+
+            if let Frame::Data(bytes) = next {
+                let size = bytes.len();
+
+                // flush first if this element would push the batch over max_bytes
+                let flushed = self.would_exceed_bytes(size) && !self.cur_batch.is_empty();
+
+                if flushed {
+                    self.send_batch(ctx)?;
+                }
+
+                match I::from_buffer_frame(Frame::Data(bytes)) {
+                    Frame::Data(data) => {
+                        self.cur_batch.push(data);
+                        self.cur_bytes += size;
+                        self.started_at.get_or_insert(now);
                     }
+                    _ => self.send(ctx, Frame::Error)?,
+                }
+
+                // an element bigger than max_bytes goes out in a batch of its own
+                if self.cur_batch.len() >= self.batch_size || self.would_exceed_bytes(0) {
+                    self.send_batch(ctx)?;
+                    break; //send at most one batch per tick
+                }
+
+                if flushed {
+                    break;
                 }
             }
         }
 
+        if self.waited_too_long(now) {
+            self.send_batch(ctx)?;
+        }
+
         Ok(())
     }
 
+    fn on_finish(&mut self, ctx: &mut MxlNodeCtx) -> Result<()> {
+        self.send_batch(ctx)
+    }
+
     fn default_label(&self) -> Option<String> {
         Some(format!("Batch[{}]", self.batch_size))
     }
@@ -69,3 +154,69 @@ where
     type Input = I;
     type Output = Vec<I>;
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::test_util::{drain, push_open, tick, Frames, ManualClock};
+    use crate::{transform, MxlGraph};
+
+    #[test]
+    fn batch_with_flushes_on_bytes_and_wait() {
+        let now = Arc::new(AtomicU64::new(100));
+
+        let mut g = MxlGraph::new();
+        g.set_clock(ManualClock(now.clone()));
+
+        let batch = g.transform(transform::batch_with::<String>(10, 4, Duration::from_secs(5)).unwrap());
+
+        let input: Frames = Default::default();
+        let output: Frames = Default::default();
+
+        // the input stays open, so only the limits can send a batch
+        push_open(&input, vec!["ab".to_owned(), "cd".to_owned(), "e".to_owned()]);
+        tick(&mut g, batch.id(), &[(0, &input)], &output, 3);
+
+        now.store(105, Ordering::Relaxed);
+        tick(&mut g, batch.id(), &[(0, &input)], &output, 1);
+
+        let batches: Vec<_> = drain::<Vec<String>>(&output)
+            .into_iter()
+            .map(|frame| format!("{:?}", frame))
+            .collect();
+
+        assert_eq!(batches, vec![r#"Data(["ab", "cd"])"#, r#"Data(["e"])"#]);
+    }
+
+    #[test]
+    fn batch_with_rejects_sub_second_wait() {
+        assert!(transform::batch_with::<String>(10, 4, Duration::from_millis(200)).is_err());
+        assert!(transform::batch_with::<String>(10, 4, Duration::from_secs(1)).is_ok());
+    }
+
+    #[test]
+    fn errors_are_forwarded_without_closing_the_batch() {
+        let mut g = MxlGraph::new();
+        let batch = g.transform(transform::batch::<u64>(2));
+
+        let input: Frames = Default::default();
+        let output: Frames = Default::default();
+
+        push_open(&input, vec![1u64]);
+        input.borrow_mut().push_back(Frame::Error);
+        // too short to decode as a u64
+        input.borrow_mut().push_back(Frame::Data(bytes::Bytes::from_static(&[1])));
+        push_open(&input, vec![2u64]);
+        tick(&mut g, batch.id(), &[(0, &input)], &output, 1);
+
+        let frames: Vec<_> = drain::<Vec<u64>>(&output)
+            .into_iter()
+            .map(|frame| format!("{:?}", frame))
+            .collect();
+
+        assert_eq!(frames, vec!["Error", "Error", "Data([1, 2])"]);
+    }
+}
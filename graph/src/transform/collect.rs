@@ -1,7 +1,6 @@
-use std::marker::PhantomData;
+use std::{marker::PhantomData, mem};
 
-use super::MxlTransform;
-use crate::{graph::MxlNode, Frame, Result, MxlData, MxlNodeCtx};
+use crate::{Emitter, MxlData, Result, StreamOperator};
 
 pub struct CollectXform<I>
 where
@@ -23,38 +22,24 @@ where
     }
 }
 
-impl<I> MxlTransform for CollectXform<I>
+impl<I> StreamOperator for CollectXform<I>
 where
     I: MxlData,
 {
     type Input = I;
     type Output = Vec<I>;
-}
 
-impl<I> MxlNode for CollectXform<I>
-where
-    I: MxlData,
-{
-    fn tick(&mut self, ctx: &mut MxlNodeCtx) -> Result<()> {
-        if let Some(next) = self.recv(ctx) {
-            match next {
-                crate::Frame::Data(data) => {
-                    self.buf.push(data);
-                }
-                _ => (), //TODO
-            }
-        }
-
-        if ctx.recv_finished() {
-            //TODO take from Option<_> so we don't have to clone
-            self.send(ctx, Frame::Data(self.buf.clone()))?;
-            self.send(ctx, Frame::End)?;
-        }
+    fn on_element(&mut self, element: I, _out: &mut Emitter<Vec<I>>) -> Result<()> {
+        self.buf.push(element);
+        Ok(())
+    }
 
+    fn on_end_of_input(&mut self, _port: u32, out: &mut Emitter<Vec<I>>) -> Result<()> {
+        out.emit(mem::take(&mut self.buf));
         Ok(())
     }
 
-    fn default_label(&self) -> Option<String> {
+    fn label(&self) -> Option<String> {
         Some("Collect".to_owned())
     }
 }
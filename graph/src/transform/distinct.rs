@@ -0,0 +1,319 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::mem;
+
+use crate::{Emitter, MxlData, Result, StreamOperator};
+
+/// How `distinct` remembers the keys it has already seen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DistinctState {
+    /// Holds every key, never emits a duplicate
+    Exact,
+    /// Holds a Bloom filter sized for `expected_items` keys at the given false positive rate.
+    /// Memory is fixed, but a small fraction of distinct elements are dropped as duplicates.
+    Bloom {
+        expected_items: usize,
+        false_positive_rate: f64,
+    },
+}
+
+fn hash_of<K: Hash>(key: &K) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    hasher.finish()
+}
+
+struct BloomFilter {
+    bits: Vec<u64>,
+    num_bits: u64,
+    num_hashes: u32,
+}
+
+impl BloomFilter {
+    fn new(expected_items: usize, false_positive_rate: f64) -> Self {
+        let n = expected_items.max(1) as f64;
+        let p = false_positive_rate.clamp(f64::MIN_POSITIVE, 0.5);
+        let ln2 = std::f64::consts::LN_2;
+
+        let num_bits = ((-n * p.ln()) / (ln2 * ln2)).ceil().max(64.0) as u64;
+        let num_hashes = ((num_bits as f64 / n) * ln2).round().max(1.0) as u32;
+
+        Self {
+            bits: vec![0; num_bits.div_ceil(64) as usize],
+            num_bits,
+            num_hashes,
+        }
+    }
+
+    /// Sets the bits for `hash`, returning true if they were all set already
+    fn insert(&mut self, hash: u64) -> bool {
+        // double hashing, derives every probe from the two halves of one hash
+        let h1 = hash & 0xffff_ffff;
+        let h2 = (hash >> 32) | 1;
+        let mut present = true;
+
+        for i in 0..self.num_hashes as u64 {
+            let bit = h1.wrapping_add(i.wrapping_mul(h2)) % self.num_bits;
+            let (word, mask) = ((bit / 64) as usize, 1u64 << (bit % 64));
+
+            if self.bits[word] & mask == 0 {
+                present = false;
+                self.bits[word] |= mask;
+            }
+        }
+
+        present
+    }
+}
+
+enum Seen<K> {
+    Exact(HashSet<K>),
+    Bloom(BloomFilter),
+}
+
+impl<K: Eq + Hash> Seen<K> {
+    fn new(state: DistinctState) -> Self {
+        match state {
+            DistinctState::Exact => Seen::Exact(HashSet::new()),
+            DistinctState::Bloom {
+                expected_items,
+                false_positive_rate,
+            } => Seen::Bloom(BloomFilter::new(expected_items, false_positive_rate)),
+        }
+    }
+
+    /// Returns true the first time a key is seen
+    fn insert(&mut self, key: K) -> bool {
+        match self {
+            Seen::Exact(keys) => keys.insert(key),
+            Seen::Bloom(filter) => !filter.insert(hash_of(&key)),
+        }
+    }
+}
+
+/// Emits the first element for every key `key_fn` returns and drops the rest
+pub struct DistinctXform<I, K, F> {
+    key_fn: F,
+    seen: Seen<K>,
+    _in: PhantomData<I>,
+}
+
+impl<I, K, F> DistinctXform<I, K, F>
+where
+    I: MxlData,
+    K: Eq + Hash,
+    F: Fn(&I) -> K,
+{
+    pub fn new(key_fn: F, state: DistinctState) -> Self {
+        Self {
+            key_fn,
+            seen: Seen::new(state),
+            _in: Default::default(),
+        }
+    }
+}
+
+impl<I, K, F> StreamOperator for DistinctXform<I, K, F>
+where
+    I: MxlData,
+    K: Eq + Hash,
+    F: Fn(&I) -> K,
+{
+    type Input = I;
+    type Output = I;
+
+    fn on_element(&mut self, element: I, out: &mut Emitter<I>) -> Result<()> {
+        if self.seen.insert((self.key_fn)(&element)) {
+            out.emit(element);
+        }
+
+        Ok(())
+    }
+
+    fn label(&self) -> Option<String> {
+        Some("Distinct".to_owned())
+    }
+}
+
+/// Which record `dedupe_by` keeps when several share a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Keep {
+    /// Emits the first record as soon as it arrives and drops later ones
+    #[default]
+    First,
+    /// Holds the latest record for every key and emits them once the input ends, in the order
+    /// their keys were first seen
+    Latest,
+}
+
+/// Drops records that share a key with another record, see `Keep`
+pub struct DedupeXform<I, K, F> {
+    key_fn: F,
+    keep: Keep,
+    seen: HashSet<K>,
+    /// slot in `latest` for every key, only used with `Keep::Latest`
+    slots: HashMap<K, usize>,
+    latest: Vec<I>,
+}
+
+impl<I, K, F> DedupeXform<I, K, F>
+where
+    I: MxlData,
+    K: Eq + Hash,
+    F: Fn(&I) -> K,
+{
+    pub fn new(key_fn: F, keep: Keep) -> Self {
+        Self {
+            key_fn,
+            keep,
+            seen: HashSet::new(),
+            slots: HashMap::new(),
+            latest: Vec::new(),
+        }
+    }
+}
+
+impl<I, K, F> StreamOperator for DedupeXform<I, K, F>
+where
+    I: MxlData,
+    K: Eq + Hash,
+    F: Fn(&I) -> K,
+{
+    type Input = I;
+    type Output = I;
+
+    fn on_element(&mut self, element: I, out: &mut Emitter<I>) -> Result<()> {
+        let key = (self.key_fn)(&element);
+
+        match self.keep {
+            Keep::First => {
+                if self.seen.insert(key) {
+                    out.emit(element);
+                }
+            }
+            Keep::Latest => match self.slots.get(&key) {
+                Some(slot) => self.latest[*slot] = element,
+                None => {
+                    self.slots.insert(key, self.latest.len());
+                    self.latest.push(element);
+                }
+            },
+        }
+
+        Ok(())
+    }
+
+    fn on_end_of_input(&mut self, _port: u32, out: &mut Emitter<I>) -> Result<()> {
+        self.slots.clear();
+        out.emit_all(mem::take(&mut self.latest));
+        Ok(())
+    }
+
+    fn label(&self) -> Option<String> {
+        Some(format!("Dedupe[{:?}]", self.keep))
+    }
+}
+
+/// Estimates the number of distinct elements with a HyperLogLog sketch, emitting the estimate
+/// once the input ends. Uses `2^precision` bytes of state and has a standard error of about
+/// `1.04 / sqrt(2^precision)`.
+pub struct ApproxCountDistinctXform<I> {
+    precision: u32,
+    registers: Vec<u8>,
+    _in: PhantomData<I>,
+}
+
+impl<I: MxlData + Hash> ApproxCountDistinctXform<I> {
+    /// `precision` is clamped to 4..=16
+    pub fn new(precision: u32) -> Self {
+        let precision = precision.clamp(4, 16);
+
+        Self {
+            precision,
+            registers: vec![0; 1 << precision],
+            _in: Default::default(),
+        }
+    }
+
+    fn estimate(&self) -> u64 {
+        let m = self.registers.len() as f64;
+        let alpha = match self.registers.len() {
+            16 => 0.673,
+            32 => 0.697,
+            64 => 0.709,
+            _ => 0.7213 / (1.0 + 1.079 / m),
+        };
+
+        let sum: f64 = self.registers.iter().map(|r| 2f64.powi(-(*r as i32))).sum();
+        let raw = alpha * m * m / sum;
+
+        let zeros = self.registers.iter().filter(|r| **r == 0).count();
+
+        // small cardinalities are estimated better by linear counting
+        if raw <= 2.5 * m && zeros > 0 {
+            (m * (m / zeros as f64).ln()).round() as u64
+        } else {
+            raw.round() as u64
+        }
+    }
+}
+
+impl<I: MxlData + Hash> StreamOperator for ApproxCountDistinctXform<I> {
+    type Input = I;
+    type Output = u64;
+
+    fn on_element(&mut self, element: I, _out: &mut Emitter<u64>) -> Result<()> {
+        let hash = hash_of(&element);
+
+        let index = (hash >> (64 - self.precision)) as usize;
+        let rest = hash << self.precision;
+        let rank = (rest.leading_zeros() + 1).min(64 - self.precision + 1) as u8;
+
+        if rank > self.registers[index] {
+            self.registers[index] = rank;
+        }
+
+        Ok(())
+    }
+
+    fn on_end_of_input(&mut self, _port: u32, out: &mut Emitter<u64>) -> Result<()> {
+        out.emit(self.estimate());
+        Ok(())
+    }
+
+    fn label(&self) -> Option<String> {
+        Some("ApproxCountDistinct".to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{data, drain, push, tick, Frames};
+    use crate::{transform, MxlGraph, KV};
+
+    #[test]
+    fn dedupe_by_keeps_latest_record_in_first_seen_order() {
+        let mut g = MxlGraph::new();
+        let deduped = g.transform(transform::dedupe_by(
+            |kv: &KV<String, u32>| kv.key().clone(),
+            Keep::Latest,
+        ));
+
+        let input: Frames = Default::default();
+        let output: Frames = Default::default();
+
+        let kv = |k: &str, v: u32| KV(k.to_owned(), v);
+        push(&input, vec![kv("a", 1), kv("b", 1), kv("a", 2), kv("c", 1), kv("b", 2)]);
+        tick(&mut g, deduped.id(), &[(0, &input)], &output, 10);
+
+        let records: Vec<_> = data(drain::<KV<String, u32>>(&output))
+            .into_iter()
+            .map(|KV(k, v)| format!("{}{}", k, v))
+            .collect();
+
+        assert_eq!(records, vec!["a2", "b2", "c1"]);
+    }
+}
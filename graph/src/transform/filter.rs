@@ -51,10 +51,6 @@ where
             }
         }
 
-        if ctx.recv_finished() {
-            self.send(ctx, Frame::End)?;
-        }
-
         Ok(())
     }
 
@@ -1,7 +1,6 @@
 use std::marker::PhantomData;
 
-use super::MxlTransform;
-use crate::{graph::MxlNode, Frame, Result, MxlData};
+use crate::{Emitter, MxlData, Result, StreamOperator};
 
 pub struct FlattenXform<I>
 where
@@ -21,38 +20,19 @@ where
     }
 }
 
-impl<I> MxlTransform for FlattenXform<I>
+impl<I> StreamOperator for FlattenXform<I>
 where
     I: MxlData,
 {
     type Input = Vec<I>;
     type Output = I;
-}
-
-impl<I> MxlNode for FlattenXform<I>
-where
-    I: MxlData,
-{
-    fn tick(&mut self, ctx: &mut crate::graph::MxlNodeCtx) -> Result<()> {
-        if let Some(next) = self.recv(ctx) {
-            match next {
-                crate::Frame::Data(data) => {
-                    for d in data {
-                        self.send(ctx, Frame::Data(d))?
-                    }
-                }
-                _ => (), //TODO
-            }
-
-            if ctx.recv_finished() {
-                self.send(ctx, Frame::End)?;
-            }
-        }
 
+    fn on_element(&mut self, element: Vec<I>, out: &mut Emitter<I>) -> Result<()> {
+        out.emit_all(element);
         Ok(())
     }
 
-    fn default_label(&self) -> Option<String> {
+    fn label(&self) -> Option<String> {
         Some("Flatten".to_owned())
     }
 }
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 use std::hash::Hash;
+use std::mem;
 
 use crate::{
     graph::{MxlNode, MxlNodeCtx},
@@ -13,8 +14,7 @@ where
     K: MxlData + Eq + Hash,
     V: MxlData,
 {
-    buffer: Option<HashMap<K, Vec<V>>>, //FIXME introduce a state enum to get rid of panics in tick logic
-    buffering: bool,
+    buffer: HashMap<K, Vec<V>>,
 }
 
 impl<K, V> GroupByKey<K, V>
@@ -24,8 +24,7 @@ where
 {
     pub(crate) fn new() -> Self {
         Self {
-            buffer: Some(HashMap::new()),
-            buffering: true,
+            buffer: HashMap::new(),
         }
     }
 }
@@ -45,34 +44,17 @@ where
     V: MxlData,
 {
     fn tick(&mut self, ctx: &mut MxlNodeCtx) -> Result<()> {
-        if self.buffering {
-            if let Some(frame) = self.recv(ctx) {
-                match frame {
-                    crate::Frame::Data(data) => {
-                        if let Some(buffer) = self.buffer.as_mut() {
-                            let (key, value) = data.into_parts();
-                            if !buffer.contains_key(&key) {
-                                buffer.insert(key.clone(), Vec::new());
-                            }
+        if let Some(Frame::Data(data)) = self.recv(ctx) {
+            let (key, value) = data.into_parts();
+            self.buffer.entry(key).or_default().push(value);
+        }
 
-                            let key_buffer = buffer.get_mut(&key).unwrap();
-                            key_buffer.push(value)
-                        } else {
-                            panic!("invalid buffer state")
-                        }
-                    }
-                    crate::Frame::Error => (),
-                    crate::Frame::End => self.buffering = false,
-                }
-            }
-        } else {
-            if let Some(mut buffer) = self.buffer.take() {
-                for (k, v) in buffer.drain() {
-                    self.send(ctx, Frame::Data(KV(k, v)))?;
-                }
+        Ok(())
+    }
 
-                self.send(ctx, Frame::End)?;
-            }
+    fn on_finish(&mut self, ctx: &mut MxlNodeCtx) -> Result<()> {
+        for (k, v) in mem::take(&mut self.buffer) {
+            self.send(ctx, Frame::Data(KV(k, v)))?;
         }
 
         Ok(())
@@ -0,0 +1,354 @@
+use std::marker::PhantomData;
+
+use super::MxlTransform;
+use crate::graph::{MxlNode, MxlNodeCtx};
+use crate::{Emitter, Frame, MxlData, Result, StreamOperator, KV};
+
+/// Emits every element of the iterator `func` returns for each input element
+pub struct FlatMapXform<I, O, It, F> {
+    func: F,
+    _io: PhantomData<(I, O, It)>,
+}
+
+impl<I, O, It, F> FlatMapXform<I, O, It, F>
+where
+    I: MxlData,
+    O: MxlData,
+    It: IntoIterator<Item = O>,
+    F: Fn(I) -> It,
+{
+    pub fn new(func: F) -> Self {
+        Self {
+            func,
+            _io: Default::default(),
+        }
+    }
+}
+
+impl<I, O, It, F> StreamOperator for FlatMapXform<I, O, It, F>
+where
+    I: MxlData,
+    O: MxlData,
+    It: IntoIterator<Item = O>,
+    F: Fn(I) -> It,
+{
+    type Input = I;
+    type Output = O;
+
+    fn on_element(&mut self, element: I, out: &mut Emitter<O>) -> Result<()> {
+        out.emit_all((self.func)(element));
+        Ok(())
+    }
+
+    fn label(&self) -> Option<String> {
+        Some("FlatMap".to_owned())
+    }
+}
+
+/// Maps elements with `func`, dropping those it returns `None` for
+pub struct FilterMapXform<I, O, F> {
+    func: F,
+    _io: PhantomData<(I, O)>,
+}
+
+impl<I, O, F> FilterMapXform<I, O, F>
+where
+    I: MxlData,
+    O: MxlData,
+    F: Fn(I) -> Option<O>,
+{
+    pub fn new(func: F) -> Self {
+        Self {
+            func,
+            _io: Default::default(),
+        }
+    }
+}
+
+impl<I, O, F> StreamOperator for FilterMapXform<I, O, F>
+where
+    I: MxlData,
+    O: MxlData,
+    F: Fn(I) -> Option<O>,
+{
+    type Input = I;
+    type Output = O;
+
+    fn on_element(&mut self, element: I, out: &mut Emitter<O>) -> Result<()> {
+        if let Some(o) = (self.func)(element) {
+            out.emit(o);
+        }
+
+        Ok(())
+    }
+
+    fn label(&self) -> Option<String> {
+        Some("FilterMap".to_owned())
+    }
+}
+
+/// Like `FilterMapXform` with a fallible `func`, an error is returned from the node's tick
+pub struct TryFilterMapXform<I, O, F> {
+    func: F,
+    _io: PhantomData<(I, O)>,
+}
+
+impl<I, O, F> TryFilterMapXform<I, O, F>
+where
+    I: MxlData,
+    O: MxlData,
+    F: Fn(I) -> Result<Option<O>>,
+{
+    pub fn new(func: F) -> Self {
+        Self {
+            func,
+            _io: Default::default(),
+        }
+    }
+}
+
+impl<I, O, F> StreamOperator for TryFilterMapXform<I, O, F>
+where
+    I: MxlData,
+    O: MxlData,
+    F: Fn(I) -> Result<Option<O>>,
+{
+    type Input = I;
+    type Output = O;
+
+    fn on_element(&mut self, element: I, out: &mut Emitter<O>) -> Result<()> {
+        if let Some(o) = (self.func)(element)? {
+            out.emit(o);
+        }
+
+        Ok(())
+    }
+
+    fn label(&self) -> Option<String> {
+        Some("TryFilterMap".to_owned())
+    }
+}
+
+/// Calls `func` with every element and passes the element through unchanged
+pub struct InspectXform<I, F> {
+    func: F,
+    _i: PhantomData<I>,
+}
+
+impl<I, F> InspectXform<I, F>
+where
+    I: MxlData,
+    F: Fn(&I),
+{
+    pub fn new(func: F) -> Self {
+        Self {
+            func,
+            _i: Default::default(),
+        }
+    }
+}
+
+impl<I, F> StreamOperator for InspectXform<I, F>
+where
+    I: MxlData,
+    F: Fn(&I),
+{
+    type Input = I;
+    type Output = I;
+
+    fn on_element(&mut self, element: I, out: &mut Emitter<I>) -> Result<()> {
+        (self.func)(&element);
+        out.emit(element);
+        Ok(())
+    }
+
+    fn label(&self) -> Option<String> {
+        Some("Inspect".to_owned())
+    }
+}
+
+/// Emits the first `n` elements, then stops the node so End is sent without waiting for the
+/// rest of the input
+pub struct TakeXform<I> {
+    remaining: usize,
+    n: usize,
+    _i: PhantomData<I>,
+}
+
+impl<I: MxlData> TakeXform<I> {
+    pub fn new(n: usize) -> Self {
+        Self {
+            remaining: n,
+            n,
+            _i: Default::default(),
+        }
+    }
+}
+
+impl<I: MxlData> MxlTransform for TakeXform<I> {
+    type Input = I;
+    type Output = I;
+}
+
+impl<I: MxlData> MxlNode for TakeXform<I> {
+    fn tick(&mut self, ctx: &mut MxlNodeCtx) -> Result<()> {
+        if self.remaining > 0 {
+            if let Some(Frame::Data(data)) = self.recv(ctx) {
+                self.remaining -= 1;
+                self.send(ctx, Frame::Data(data))?;
+            }
+        }
+
+        if self.remaining == 0 {
+            ctx.stop();
+        }
+
+        Ok(())
+    }
+
+    fn default_label(&self) -> Option<String> {
+        Some(format!("Take[{}]", self.n))
+    }
+}
+
+/// Drops the first `n` elements and emits the rest
+pub struct SkipXform<I> {
+    remaining: usize,
+    n: usize,
+    _i: PhantomData<I>,
+}
+
+impl<I: MxlData> SkipXform<I> {
+    pub fn new(n: usize) -> Self {
+        Self {
+            remaining: n,
+            n,
+            _i: Default::default(),
+        }
+    }
+}
+
+impl<I: MxlData> StreamOperator for SkipXform<I> {
+    type Input = I;
+    type Output = I;
+
+    fn on_element(&mut self, element: I, out: &mut Emitter<I>) -> Result<()> {
+        if self.remaining > 0 {
+            self.remaining -= 1;
+        } else {
+            out.emit(element);
+        }
+
+        Ok(())
+    }
+
+    fn label(&self) -> Option<String> {
+        Some(format!("Skip[{}]", self.n))
+    }
+}
+
+/// Pairs every element with its position in the input, starting at 0
+pub struct EnumerateXform<I> {
+    next: u64,
+    _i: PhantomData<I>,
+}
+
+impl<I: MxlData> EnumerateXform<I> {
+    pub fn new() -> Self {
+        Self {
+            next: 0,
+            _i: Default::default(),
+        }
+    }
+}
+
+impl<I: MxlData> Default for EnumerateXform<I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I: MxlData> StreamOperator for EnumerateXform<I> {
+    type Input = I;
+    type Output = KV<u64, I>;
+
+    fn on_element(&mut self, element: I, out: &mut Emitter<KV<u64, I>>) -> Result<()> {
+        out.emit(KV(self.next, element));
+        self.next += 1;
+        Ok(())
+    }
+
+    fn label(&self) -> Option<String> {
+        Some("Enumerate".to_owned())
+    }
+}
+
+/// Threads a state through the elements, emitting what `func` returns for each one, e.g. a
+/// running total
+pub struct ScanXform<I, O, S, F> {
+    state: S,
+    func: F,
+    _io: PhantomData<(I, O)>,
+}
+
+impl<I, O, S, F> ScanXform<I, O, S, F>
+where
+    I: MxlData,
+    O: MxlData,
+    F: Fn(&mut S, I) -> O,
+{
+    pub fn new(state: S, func: F) -> Self {
+        Self {
+            state,
+            func,
+            _io: Default::default(),
+        }
+    }
+}
+
+impl<I, O, S, F> StreamOperator for ScanXform<I, O, S, F>
+where
+    I: MxlData,
+    O: MxlData,
+    F: Fn(&mut S, I) -> O,
+{
+    type Input = I;
+    type Output = O;
+
+    fn on_element(&mut self, element: I, out: &mut Emitter<O>) -> Result<()> {
+        out.emit((self.func)(&mut self.state, element));
+        Ok(())
+    }
+
+    fn label(&self) -> Option<String> {
+        Some("Scan".to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_util::{drain, push_open, tick, Frames};
+    use crate::{transform, MxlGraph};
+
+    #[test]
+    fn take_sends_end_before_input_ends() {
+        let mut g = MxlGraph::new();
+        let take = g.transform(transform::take::<String>(2));
+
+        let input: Frames = Default::default();
+        let output: Frames = Default::default();
+
+        // the input never ends
+        push_open(&input, vec!["a".to_owned(), "b".to_owned(), "c".to_owned()]);
+        tick(&mut g, take.id(), &[(0, &input)], &output, 5);
+
+        assert!(g.is_finished(&take.id()));
+
+        let frames: Vec<_> = drain::<String>(&output)
+            .into_iter()
+            .map(|frame| format!("{:?}", frame))
+            .collect();
+
+        assert_eq!(frames, vec![r#"Data("a")"#, r#"Data("b")"#, "End"]);
+    }
+}
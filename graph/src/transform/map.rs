@@ -53,10 +53,6 @@ where
             }
         }
 
-        if ctx.recv_finished() {
-            self.send(ctx, Frame::End)?;
-        }
-
         Ok(())
     }
 
@@ -115,10 +111,6 @@ where
             }
         }
 
-        if ctx.recv_finished() {
-            self.send(ctx, Frame::End)?;
-        }
-
         Ok(())
     }
 
@@ -1,22 +1,40 @@
+mod aggregate;
 mod batch;
 mod collect;
+mod distinct;
 mod filter;
 mod flatten;
 mod groupby;
+mod iter;
 mod map;
+mod sort;
 mod to_json;
+mod top_k;
+mod window;
 
-use std::{fmt::Display, marker::PhantomData};
+use std::{fmt::Display, marker::PhantomData, time::Duration};
 
 use crate::graph::{MxlNode, MxlNodeCtx};
-use crate::{Frame, Result, MxlData};
+use crate::{Frame, MxlData, OperatorNode, Result};
 
 use anyhow::anyhow;
 use serde::Serialize;
 
+pub use self::aggregate::{
+    AggregateByKey, Aggregator, AsF64, CombineValues, Count, DistinctCount, Fold, Max, Mean, Min, Reduce,
+    Sum,
+};
+pub use self::distinct::{ApproxCountDistinctXform, DedupeXform, DistinctState, DistinctXform, Keep};
 pub use self::filter::FilterXform;
 pub use self::groupby::GroupByKey;
+pub use self::iter::{
+    EnumerateXform, FilterMapXform, FlatMapXform, InspectXform, ScanXform, SkipXform, TakeXform,
+    TryFilterMapXform,
+};
 pub use self::map::{MapXform, TryMapXform};
+pub use self::sort::{ByCmp, ByKey, SortConfig, SortOrder, SortXform};
+pub use self::top_k::TopKXform;
+pub use self::window::{LateData, Window, WindowXform};
 
 pub trait MxlTransform: MxlNode {
     type Input: MxlData;
@@ -51,10 +69,8 @@ pub struct LowercaseXform;
 
 impl MxlNode for UppercaseXform {
     fn tick(&mut self, ctx: &mut MxlNodeCtx) -> Result<()> {
-        match self.recv(ctx) {
-            Some(Frame::Data(data)) => self.send(ctx, Frame::Data(data.to_uppercase()))?,
-            Some(Frame::End) => self.send(ctx, Frame::End)?,
-            _ => (),
+        if let Some(Frame::Data(data)) = self.recv(ctx) {
+            self.send(ctx, Frame::Data(data.to_uppercase()))?;
         }
 
         Ok(())
@@ -70,10 +86,8 @@ pub struct UppercaseXform;
 
 impl MxlNode for LowercaseXform {
     fn tick(&mut self, ctx: &mut MxlNodeCtx) -> Result<()> {
-        match self.recv(ctx) {
-            Some(Frame::Data(data)) => self.send(ctx, Frame::Data(data.to_lowercase()))?,
-            Some(Frame::End) => self.send(ctx, Frame::End)?,
-            _ => (),
+        if let Some(Frame::Data(data)) = self.recv(ctx) {
+            self.send(ctx, Frame::Data(data.to_lowercase()))?;
         }
 
         Ok(())
@@ -85,28 +99,29 @@ impl MxlTransform for LowercaseXform {
     type Output = String;
 }
 
-pub struct CountXform {
-    state: u32,
+/// Counts the elements of its input, emitting the total once the input ends
+pub struct CountXform<I: MxlData> {
+    state: u64,
+    _in: PhantomData<I>,
 }
 
-impl MxlTransform for CountXform {
-    type Input = String; //TODO how can we abstract over any type?
-    type Output = u32;
+impl<I: MxlData> MxlTransform for CountXform<I> {
+    type Input = I;
+    type Output = u64;
 }
 
-impl MxlNode for CountXform {
+impl<I: MxlData> MxlNode for CountXform<I> {
     fn tick(&mut self, ctx: &mut MxlNodeCtx) -> Result<()> {
-        match self.recv(ctx) {
-            Some(Frame::Data(_data)) => self.state = self.state + 1,
-            Some(Frame::End) => {
-                self.send(ctx, Frame::Data(self.state))?;
-                self.send(ctx, Frame::End)?;
-            }
-            _ => (),
+        if let Some(Frame::Data(_data)) = self.recv(ctx) {
+            self.state += 1;
         }
 
         Ok(())
     }
+
+    fn on_finish(&mut self, ctx: &mut MxlNodeCtx) -> Result<()> {
+        self.send(ctx, Frame::Data(self.state))
+    }
 }
 
 pub struct ToStringXform<I: Display + MxlData> {
@@ -120,11 +135,8 @@ impl<I: Display + MxlData> MxlTransform for ToStringXform<I> {
 
 impl<I: Display + MxlData> MxlNode for ToStringXform<I> {
     fn tick(&mut self, ctx: &mut MxlNodeCtx) -> Result<()> {
-        let frame = self.recv(ctx);
-        match frame {
-            Some(Frame::Data(data)) => self.send(ctx, Frame::Data(format!("{}", data)))?,
-            Some(Frame::End) => self.send(ctx, Frame::End)?,
-            _ => (),
+        if let Some(Frame::Data(data)) = self.recv(ctx) {
+            self.send(ctx, Frame::Data(format!("{}", data)))?;
         }
 
         Ok(())
@@ -142,11 +154,8 @@ impl<I: std::fmt::Debug + MxlData> MxlTransform for ToDebugStringXform<I> {
 
 impl<I: std::fmt::Debug + MxlData> MxlNode for ToDebugStringXform<I> {
     fn tick(&mut self, ctx: &mut MxlNodeCtx) -> Result<()> {
-        let frame = self.recv(ctx);
-        match frame {
-            Some(Frame::Data(data)) => self.send(ctx, Frame::Data(format!("{:?}", data)))?,
-            Some(Frame::End) => self.send(ctx, Frame::End)?,
-            _ => (),
+        if let Some(Frame::Data(data)) = self.recv(ctx) {
+            self.send(ctx, Frame::Data(format!("{:?}", data)))?;
         }
 
         Ok(())
@@ -165,8 +174,69 @@ pub fn to_string<I: Display + MxlData>() -> ToStringXform<I> {
     }
 }
 
-pub fn count() -> CountXform {
-    CountXform { state: 0 }
+pub fn count<I: MxlData>() -> CountXform<I> {
+    CountXform {
+        state: 0,
+        _in: Default::default(),
+    }
+}
+
+/// Emits the first element for every key `key_fn` returns
+pub fn distinct_by<I, K, F>(key_fn: F, state: DistinctState) -> OperatorNode<DistinctXform<I, K, F>>
+where
+    I: MxlData,
+    K: Eq + std::hash::Hash,
+    F: Fn(&I) -> K,
+{
+    OperatorNode::new(DistinctXform::new(key_fn, state))
+}
+
+/// Drops records sharing a key with an earlier one, keeping the first or the latest
+pub fn dedupe_by<I, K, F>(key_fn: F, keep: Keep) -> OperatorNode<DedupeXform<I, K, F>>
+where
+    I: MxlData,
+    K: Eq + std::hash::Hash,
+    F: Fn(&I) -> K,
+{
+    OperatorNode::new(DedupeXform::new(key_fn, keep))
+}
+
+/// Estimates the number of distinct elements with a HyperLogLog sketch of `2^precision`
+/// registers
+pub fn approx_count_distinct<I>(precision: u32) -> OperatorNode<ApproxCountDistinctXform<I>>
+where
+    I: MxlData + std::hash::Hash,
+{
+    OperatorNode::new(ApproxCountDistinctXform::new(precision))
+}
+
+/// Sorts the input with `cmp` once it ends, spilling sorted runs if the config allows it
+pub fn sort_by<I, C>(cmp: C, config: SortConfig) -> SortXform<I, ByCmp<C>>
+where
+    I: MxlData,
+    C: Fn(&I, &I) -> std::cmp::Ordering,
+{
+    SortXform::new(ByCmp(cmp), config)
+}
+
+/// Sorts the input by the key `key_fn` returns once it ends, computing each element's key once
+pub fn sort_by_key<I, K, F>(key_fn: F, config: SortConfig) -> SortXform<I, ByKey<F, K>>
+where
+    I: MxlData,
+    K: Ord,
+    F: Fn(&I) -> K,
+{
+    SortXform::new(ByKey::new(key_fn), config)
+}
+
+/// Emits the `k` elements with the highest score, highest first
+pub fn top_k<I, S, F>(k: usize, score_fn: F) -> OperatorNode<TopKXform<I, S, F>>
+where
+    I: MxlData,
+    S: PartialOrd,
+    F: Fn(&I) -> S,
+{
+    OperatorNode::new(TopKXform::new(k, score_fn))
 }
 
 pub fn group_by_key<K, V>() -> GroupByKey<K, V>
@@ -177,6 +247,16 @@ where
     GroupByKey::new()
 }
 
+/// Aggregates the values of each key with `aggregator`, e.g. `Sum` or `Mean`
+pub fn aggregate_by_key<K, V, A>(aggregator: A) -> AggregateByKey<K, V, A>
+where
+    K: MxlData + Eq + std::hash::Hash,
+    V: MxlData,
+    A: Aggregator<V>,
+{
+    AggregateByKey::new(aggregator)
+}
+
 pub fn try_map<I, O, F>(f: F) -> TryMapXform<I, O, F>
 where
     I: MxlData,
@@ -195,6 +275,63 @@ where
     map::MapXform::new(f)
 }
 
+pub fn flat_map<I, O, It, F>(f: F) -> OperatorNode<FlatMapXform<I, O, It, F>>
+where
+    I: MxlData,
+    O: MxlData,
+    It: IntoIterator<Item = O>,
+    F: Fn(I) -> It,
+{
+    OperatorNode::new(FlatMapXform::new(f))
+}
+
+pub fn filter_map<I, O, F>(f: F) -> OperatorNode<FilterMapXform<I, O, F>>
+where
+    I: MxlData,
+    O: MxlData,
+    F: Fn(I) -> Option<O>,
+{
+    OperatorNode::new(FilterMapXform::new(f))
+}
+
+pub fn try_filter_map<I, O, F>(f: F) -> OperatorNode<TryFilterMapXform<I, O, F>>
+where
+    I: MxlData,
+    O: MxlData,
+    F: Fn(I) -> Result<Option<O>>,
+{
+    OperatorNode::new(TryFilterMapXform::new(f))
+}
+
+pub fn inspect<I, F>(f: F) -> OperatorNode<InspectXform<I, F>>
+where
+    I: MxlData,
+    F: Fn(&I),
+{
+    OperatorNode::new(InspectXform::new(f))
+}
+
+pub fn take<I: MxlData>(n: usize) -> TakeXform<I> {
+    TakeXform::new(n)
+}
+
+pub fn skip<I: MxlData>(n: usize) -> OperatorNode<SkipXform<I>> {
+    OperatorNode::new(SkipXform::new(n))
+}
+
+pub fn enumerate<I: MxlData>() -> OperatorNode<EnumerateXform<I>> {
+    OperatorNode::new(EnumerateXform::new())
+}
+
+pub fn scan<I, O, S, F>(state: S, f: F) -> OperatorNode<ScanXform<I, O, S, F>>
+where
+    I: MxlData,
+    O: MxlData,
+    F: Fn(&mut S, I) -> O,
+{
+    OperatorNode::new(ScanXform::new(state, f))
+}
+
 pub fn filter<I, F>(f: F) -> FilterXform<I, F>
 where
     I: MxlData,
@@ -203,18 +340,18 @@ where
     filter::FilterXform::new(f)
 }
 
-pub fn flatten<I>() -> flatten::FlattenXform<I>
+pub fn flatten<I>() -> OperatorNode<flatten::FlattenXform<I>>
 where
     I: MxlData,
 {
-    flatten::FlattenXform::new()
+    OperatorNode::new(flatten::FlattenXform::new())
 }
 
-pub fn collect<I>() -> collect::CollectXform<I>
+pub fn collect<I>() -> OperatorNode<collect::CollectXform<I>>
 where
     I: MxlData,
 {
-    collect::CollectXform::new()
+    OperatorNode::new(collect::CollectXform::new())
 }
 
 pub fn to_json<I>() -> to_json::ToJsonXform<I>
@@ -230,3 +367,40 @@ where
 {
     batch::BatchXform::new(size)
 }
+
+/// Batches of at most `max_items` elements and `max_bytes` of encoded elements, a partial
+/// batch is sent once it's waited `max_wait`. Fails if `max_wait` is under a second.
+pub fn batch_with<I>(max_items: usize, max_bytes: usize, max_wait: Duration) -> Result<batch::BatchXform<I>>
+where
+    I: MxlData,
+{
+    batch::BatchXform::with_limits(max_items, max_bytes, max_wait)
+}
+
+/// Windows of `size` that don't overlap, `timestamp` returns an element's event time in
+/// milliseconds
+pub fn tumbling_window<I, F>(size: Duration, timestamp: F) -> WindowXform<I, F>
+where
+    I: MxlData,
+    F: Fn(&I) -> u64,
+{
+    WindowXform::tumbling(size, timestamp)
+}
+
+/// Windows of `size` starting every `slide`
+pub fn sliding_window<I, F>(size: Duration, slide: Duration, timestamp: F) -> WindowXform<I, F>
+where
+    I: MxlData,
+    F: Fn(&I) -> u64,
+{
+    WindowXform::sliding(size, slide, timestamp)
+}
+
+/// Windows that close once no element arrived for `gap`
+pub fn session_window<I, F>(gap: Duration, timestamp: F) -> WindowXform<I, F>
+where
+    I: MxlData,
+    F: Fn(&I) -> u64,
+{
+    WindowXform::session(gap, timestamp)
+}
@@ -0,0 +1,404 @@
+use std::cmp::Ordering;
+use std::io::{Read, Write};
+use std::marker::PhantomData;
+use std::mem;
+
+use anyhow::anyhow;
+
+use crate::spill::{read_frame, run_prefix, write_frame, SpillStore};
+use crate::{graph::MxlNode, Frame, MxlData, MxlNodeCtx, MxlTransform, Result};
+
+/// Tunes how much of its input a sort holds in memory
+pub struct SortConfig {
+    spill: Option<Box<dyn SpillStore>>,
+    max_in_memory: usize,
+    limit: Option<usize>,
+}
+
+impl Default for SortConfig {
+    fn default() -> Self {
+        Self {
+            spill: None,
+            max_in_memory: usize::MAX,
+            limit: None,
+        }
+    }
+}
+
+impl SortConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Once `max_in_memory` elements are buffered, sorts them and writes them to `store` as a
+    /// run. The runs are merged once the input ends.
+    pub fn spill<S: SpillStore + 'static>(mut self, store: S, max_in_memory: usize) -> Self {
+        self.spill = Some(Box::new(store));
+        self.max_in_memory = max_in_memory.max(1);
+        self
+    }
+
+    /// Only emits the first `limit` elements. Only that many are kept, so nothing is spilled.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+}
+
+/// How a `SortXform` orders its elements. The key is computed once per element as it's
+/// buffered or read back from a run, and kept next to it for every comparison.
+pub trait SortOrder<I> {
+    type Key;
+
+    fn key(&self, element: &I) -> Self::Key;
+
+    fn compare(&self, a: &(Self::Key, I), b: &(Self::Key, I)) -> Ordering;
+}
+
+/// Orders elements with a comparator, there's no key to compute
+pub struct ByCmp<C>(pub C);
+
+impl<I, C> SortOrder<I> for ByCmp<C>
+where
+    C: Fn(&I, &I) -> Ordering,
+{
+    type Key = ();
+
+    fn key(&self, _element: &I) -> Self::Key {}
+
+    fn compare(&self, a: &((), I), b: &((), I)) -> Ordering {
+        (self.0)(&a.1, &b.1)
+    }
+}
+
+/// Orders elements by the key a function returns
+pub struct ByKey<F, K> {
+    key_fn: F,
+    _k: PhantomData<fn() -> K>,
+}
+
+impl<F, K> ByKey<F, K> {
+    pub fn new(key_fn: F) -> Self {
+        Self {
+            key_fn,
+            _k: PhantomData,
+        }
+    }
+}
+
+impl<I, K, F> SortOrder<I> for ByKey<F, K>
+where
+    K: Ord,
+    F: Fn(&I) -> K,
+{
+    type Key = K;
+
+    fn key(&self, element: &I) -> K {
+        (self.key_fn)(element)
+    }
+
+    fn compare(&self, a: &(K, I), b: &(K, I)) -> Ordering {
+        a.0.cmp(&b.0)
+    }
+}
+
+/// A sorted run being merged, with the next element it holds
+struct RunReader<K, I> {
+    run: Box<dyn Read + Send>,
+    head: Option<(K, I)>,
+}
+
+impl<K, I: MxlData> RunReader<K, I> {
+    fn advance<O: SortOrder<I, Key = K>>(&mut self, order: &O) -> Result<()> {
+        self.head = None;
+
+        while let Some(frame) = read_frame(self.run.as_mut())? {
+            if let Frame::Data(element) = I::from_buffer_frame(frame) {
+                self.head = Some((order.key(&element), element));
+                break;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Sorts its input in `order`, emitting every element once the input ends. Equal elements
+/// keep their input order. With a spill store, input that doesn't fit in memory is written as
+/// sorted runs which are merged at the end.
+pub struct SortXform<I, O>
+where
+    I: MxlData,
+    O: SortOrder<I>,
+{
+    order: O,
+    buffer: Vec<(O::Key, I)>,
+    spill: Option<Box<dyn SpillStore>>,
+    max_in_memory: usize,
+    limit: Option<usize>,
+    prefix: String,
+    runs: Vec<String>,
+}
+
+impl<I, O> SortXform<I, O>
+where
+    I: MxlData,
+    O: SortOrder<I>,
+{
+    pub fn new(order: O, config: SortConfig) -> Self {
+        Self {
+            order,
+            buffer: Vec::new(),
+            spill: config.spill,
+            max_in_memory: config.max_in_memory,
+            limit: config.limit,
+            prefix: run_prefix("sort"),
+            runs: Vec::new(),
+        }
+    }
+
+    fn sort_buffer(&mut self) {
+        let order = &self.order;
+        self.buffer.sort_by(|a, b| order.compare(a, b));
+    }
+
+    /// Sorts the buffer and writes it to the spill store as a run
+    fn spill_run(&mut self) -> Result<()> {
+        let store = match self.spill.as_mut() {
+            Some(store) => store,
+            None => return Ok(()),
+        };
+
+        let name = format!("{}-{}", self.prefix, self.runs.len());
+        let mut run = store.create(&name)?;
+
+        let order = &self.order;
+        self.buffer.sort_by(|a, b| order.compare(a, b));
+
+        for (_, element) in self.buffer.drain(..) {
+            let frame = element
+                .into_buffer_frame()
+                .map_err(|_| anyhow!("error serializing spilled sort element"))?;
+            write_frame(run.as_mut(), frame)?;
+        }
+
+        run.flush()?;
+        self.runs.push(name);
+
+        Ok(())
+    }
+
+    /// Merges the spilled runs and what's left in memory, sending up to `limit` elements
+    fn merge_runs(&mut self, ctx: &mut MxlNodeCtx) -> Result<()> {
+        let store = match self.spill.as_mut() {
+            Some(store) => store,
+            None => return Ok(()),
+        };
+
+        let mut readers = Vec::with_capacity(self.runs.len());
+
+        for name in &self.runs {
+            let mut reader = RunReader {
+                run: store.open(name)?,
+                head: None,
+            };
+            reader.advance(&self.order)?;
+            readers.push(reader);
+        }
+
+        self.sort_buffer();
+        let mut in_memory = mem::take(&mut self.buffer).into_iter().peekable();
+        let mut remaining = self.limit.unwrap_or(usize::MAX);
+
+        while remaining > 0 {
+            // earlier runs win ties, they hold earlier input
+            let mut next: Option<usize> = None;
+
+            for (idx, reader) in readers.iter().enumerate() {
+                if let Some(head) = &reader.head {
+                    let better = match next {
+                        Some(best) => {
+                            let best = readers[best].head.as_ref().unwrap();
+                            self.order.compare(head, best) == Ordering::Less
+                        }
+                        None => true,
+                    };
+
+                    if better {
+                        next = Some(idx);
+                    }
+                }
+            }
+
+            let take_memory = match (next, in_memory.peek()) {
+                (Some(idx), Some(element)) => {
+                    let head = readers[idx].head.as_ref().unwrap();
+                    self.order.compare(element, head) == Ordering::Less
+                }
+                (None, Some(_)) => true,
+                (_, None) => false,
+            };
+
+            let element = if take_memory {
+                in_memory.next()
+            } else if let Some(idx) = next {
+                let element = readers[idx].head.take();
+                readers[idx].advance(&self.order)?;
+                element
+            } else {
+                None
+            };
+
+            match element {
+                Some((_, element)) => self.send(ctx, Frame::Data(element))?,
+                None => break,
+            }
+
+            remaining -= 1;
+        }
+
+        let store = self.spill.as_mut().unwrap();
+        for name in mem::take(&mut self.runs) {
+            store.remove(&name)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<I, O> MxlNode for SortXform<I, O>
+where
+    I: MxlData,
+    O: SortOrder<I>,
+{
+    fn tick(&mut self, ctx: &mut MxlNodeCtx) -> Result<()> {
+        if let Some(Frame::Data(data)) = self.recv(ctx) {
+            self.buffer.push((self.order.key(&data), data));
+
+            match self.limit {
+                // a top-N only ever needs the best `limit` elements
+                Some(limit) if self.buffer.len() >= limit.saturating_mul(2).max(1) => {
+                    self.sort_buffer();
+                    self.buffer.truncate(limit);
+                }
+                Some(_) => (),
+                None if self.buffer.len() >= self.max_in_memory => self.spill_run()?,
+                None => (),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn on_finish(&mut self, ctx: &mut MxlNodeCtx) -> Result<()> {
+        if !self.runs.is_empty() {
+            return self.merge_runs(ctx);
+        }
+
+        self.sort_buffer();
+
+        let limit = self.limit.unwrap_or(usize::MAX);
+        for (_, element) in mem::take(&mut self.buffer).into_iter().take(limit) {
+            self.send(ctx, Frame::Data(element))?;
+        }
+
+        Ok(())
+    }
+
+    fn default_label(&self) -> Option<String> {
+        match self.limit {
+            Some(limit) => Some(format!("Sort[limit {}]", limit)),
+            None => Some("Sort".to_owned()),
+        }
+    }
+}
+
+impl<I, O> MxlTransform for SortXform<I, O>
+where
+    I: MxlData,
+    O: SortOrder<I>,
+{
+    type Input = I;
+    type Output = I;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{data, drain, push, tick, Frames};
+    use crate::{transform, MxlGraph, KV};
+
+    #[test]
+    fn spilled_sort_merges_runs_in_order() {
+        let spill_dir = std::env::temp_dir().join(format!("mixlayer-sort-{}", std::process::id()));
+
+        let mut g = MxlGraph::new();
+        let config = SortConfig::new().spill(crate::LocalFsSpill::new(&spill_dir).unwrap(), 2);
+        let sorted = g.transform(transform::sort_by(
+            |a: &KV<String, u32>, b: &KV<String, u32>| a.value().cmp(b.value()),
+            config,
+        ));
+
+        let input: Frames = Default::default();
+        let output: Frames = Default::default();
+
+        let kv = |k: &str, v: u32| KV(k.to_owned(), v);
+        push(
+            &input,
+            vec![kv("a", 5), kv("b", 3), kv("c", 9), kv("d", 1), kv("e", 3), kv("f", 7), kv("g", 2)],
+        );
+        tick(&mut g, sorted.id(), &[(0, &input)], &output, 20);
+
+        let elements: Vec<_> = data(drain::<KV<String, u32>>(&output))
+            .into_iter()
+            .map(|KV(k, v)| format!("{}{}", k, v))
+            .collect();
+
+        // b and e are equal and keep their input order
+        assert_eq!(elements, vec!["d1", "g2", "b3", "e3", "a5", "f7", "c9"]);
+
+        std::fs::remove_dir_all(spill_dir).unwrap();
+    }
+
+    #[test]
+    fn sort_by_key_computes_each_key_once_per_pass() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let spill_dir = std::env::temp_dir().join(format!("mixlayer-sort-key-{}", std::process::id()));
+        let calls = Arc::new(AtomicUsize::new(0));
+        let key_calls = calls.clone();
+
+        let mut g = MxlGraph::new();
+        let config = SortConfig::new().spill(crate::LocalFsSpill::new(&spill_dir).unwrap(), 3);
+        let sorted = g.transform(transform::sort_by_key(
+            move |kv: &KV<String, u32>| {
+                key_calls.fetch_add(1, Ordering::Relaxed);
+                *kv.value()
+            },
+            config,
+        ));
+
+        let input: Frames = Default::default();
+        let output: Frames = Default::default();
+
+        let kv = |k: &str, v: u32| KV(k.to_owned(), v);
+        push(
+            &input,
+            vec![kv("a", 5), kv("b", 3), kv("c", 9), kv("d", 1), kv("e", 3), kv("f", 7), kv("g", 2)],
+        );
+        tick(&mut g, sorted.id(), &[(0, &input)], &output, 20);
+
+        let elements: Vec<_> = data(drain::<KV<String, u32>>(&output))
+            .into_iter()
+            .map(|KV(k, v)| format!("{}{}", k, v))
+            .collect();
+
+        assert_eq!(elements, vec!["d1", "g2", "b3", "e3", "a5", "f7", "c9"]);
+
+        // once as each element is buffered and once more as the 6 spilled ones are read back
+        assert_eq!(calls.load(Ordering::Relaxed), 7 + 6);
+
+        std::fs::remove_dir_all(spill_dir).unwrap();
+    }
+}
@@ -48,10 +48,6 @@ where
             }
         }
 
-        if ctx.recv_finished() {
-            self.send(ctx, Frame::End)?;
-        }
-
         Ok(())
     }
 
@@ -0,0 +1,110 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+
+use crate::{Emitter, MxlData, Result, StreamOperator};
+
+/// An element in the heap, ordered by score and then by arrival so ties keep the earlier one
+struct Ranked<I, S> {
+    score: S,
+    seq: Reverse<u64>,
+    element: I,
+}
+
+impl<I, S: PartialOrd> Ranked<I, S> {
+    fn rank(&self, other: &Self) -> Ordering {
+        self.score
+            .partial_cmp(&other.score)
+            .unwrap_or(Ordering::Equal)
+            .then(self.seq.cmp(&other.seq))
+    }
+}
+
+impl<I, S: PartialOrd> PartialEq for Ranked<I, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.rank(other) == Ordering::Equal
+    }
+}
+
+impl<I, S: PartialOrd> Eq for Ranked<I, S> {}
+
+impl<I, S: PartialOrd> PartialOrd for Ranked<I, S> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<I, S: PartialOrd> Ord for Ranked<I, S> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.rank(other)
+    }
+}
+
+/// Keeps the `k` elements with the highest score in a bounded heap, emitting them from the
+/// highest score down once the input ends
+pub struct TopKXform<I, S, F> {
+    k: usize,
+    score_fn: F,
+    /// min-heap of the best elements so far, the root is the first to be replaced
+    heap: BinaryHeap<Reverse<Ranked<I, S>>>,
+    seq: u64,
+}
+
+impl<I, S, F> TopKXform<I, S, F>
+where
+    I: MxlData,
+    S: PartialOrd,
+    F: Fn(&I) -> S,
+{
+    pub fn new(k: usize, score_fn: F) -> Self {
+        Self {
+            k,
+            score_fn,
+            heap: BinaryHeap::with_capacity(k + 1),
+            seq: 0,
+        }
+    }
+}
+
+impl<I, S, F> StreamOperator for TopKXform<I, S, F>
+where
+    I: MxlData,
+    S: PartialOrd,
+    F: Fn(&I) -> S,
+{
+    type Input = I;
+    type Output = I;
+
+    fn on_element(&mut self, element: I, _out: &mut Emitter<I>) -> Result<()> {
+        if self.k == 0 {
+            return Ok(());
+        }
+
+        let ranked = Ranked {
+            score: (self.score_fn)(&element),
+            seq: Reverse(self.seq),
+            element,
+        };
+        self.seq += 1;
+
+        if self.heap.len() < self.k {
+            self.heap.push(Reverse(ranked));
+        } else if let Some(mut lowest) = self.heap.peek_mut() {
+            if ranked > lowest.0 {
+                *lowest = Reverse(ranked);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn on_end_of_input(&mut self, _port: u32, out: &mut Emitter<I>) -> Result<()> {
+        // ascending order of Reverse is descending order of score
+        let ranked = std::mem::take(&mut self.heap).into_sorted_vec();
+        out.emit_all(ranked.into_iter().map(|r| r.0.element));
+        Ok(())
+    }
+
+    fn label(&self) -> Option<String> {
+        Some(format!("TopK[{}]", self.k))
+    }
+}
@@ -0,0 +1,334 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use bytes::{Buf, BufMut, Bytes};
+use log::debug;
+
+use crate::{Emitter, Frame, MxlData, Result, StreamOperator};
+
+/// Elements whose event time falls in `[start, end)`, times are milliseconds as returned by
+/// the window's timestamp function
+#[derive(Debug, Clone)]
+pub struct Window<T> {
+    pub start: u64,
+    pub end: u64,
+    /// set on panes emitted for data that arrived after the window already fired, see
+    /// `LateData::EmitLatePane`
+    pub late: bool,
+    pub elements: Vec<T>,
+}
+
+impl<T: MxlData> MxlData for Window<T> {
+    fn from_buffer_frame(frame: Frame<Bytes>) -> Frame<Self> {
+        frame.flat_map(|mut buf| {
+            // start, end and the late flag
+            if buf.remaining() < 17 {
+                return Frame::Error;
+            }
+
+            let start = buf.get_u64();
+            let end = buf.get_u64();
+            let late = buf.get_u8() != 0;
+
+            Vec::<T>::from_buffer_frame(Frame::Data(buf)).map(|elements| Window {
+                start,
+                end,
+                late,
+                elements,
+            })
+        })
+    }
+
+    fn into_buffer_frame(self) -> std::result::Result<Frame<Bytes>, ()> {
+        let elements = match self.elements.into_buffer_frame()? {
+            Frame::Data(elements) => elements,
+            _ => return Err(()),
+        };
+
+        let mut out = Vec::with_capacity(17 + elements.len());
+        out.put_u64(self.start);
+        out.put_u64(self.end);
+        out.put_u8(self.late as u8);
+        out.put(elements);
+
+        Ok(Frame::Data(out.into()))
+    }
+}
+
+/// What a window does with an element whose windows have already fired
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LateData {
+    /// Discards the element
+    #[default]
+    Drop,
+    /// Emits the element on its own in a window marked `late`
+    EmitLatePane,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Assigner {
+    Tumbling { size: u64 },
+    Sliding { size: u64, slide: u64 },
+    Session { gap: u64 },
+}
+
+impl Assigner {
+    /// Windows an element with event time `ts` belongs to
+    fn assign(&self, ts: u64) -> Vec<(u64, u64)> {
+        match *self {
+            Assigner::Tumbling { size } => {
+                let start = ts - ts % size;
+                vec![(start, start + size)]
+            }
+            Assigner::Sliding { size, slide } => {
+                let mut windows = Vec::new();
+                let mut start = ts - ts % slide;
+
+                while start + size > ts {
+                    windows.push((start, start + size));
+
+                    if start < slide {
+                        break;
+                    }
+
+                    start -= slide;
+                }
+
+                windows.reverse();
+                windows
+            }
+            Assigner::Session { gap } => vec![(ts, ts + gap)],
+        }
+    }
+}
+
+/// Groups elements into windows of event time. Windows fire once the watermark, the largest
+/// timestamp seen minus the allowed lateness, passes their end, and any windows still open fire
+/// when the input ends.
+pub struct WindowXform<T, F> {
+    timestamp: F,
+    assigner: Assigner,
+    allowed_lateness: u64,
+    late_data: LateData,
+    /// open windows keyed by `(start, end)`
+    panes: BTreeMap<(u64, u64), Vec<T>>,
+    watermark: Option<u64>,
+}
+
+impl<T, F> WindowXform<T, F>
+where
+    T: MxlData,
+    F: Fn(&T) -> u64,
+{
+    fn new(assigner: Assigner, timestamp: F) -> Self {
+        Self {
+            timestamp,
+            assigner,
+            allowed_lateness: 0,
+            late_data: LateData::default(),
+            panes: BTreeMap::new(),
+            watermark: None,
+        }
+    }
+
+    /// Fixed size windows that don't overlap
+    pub fn tumbling(size: Duration, timestamp: F) -> Self {
+        let size = (size.as_millis() as u64).max(1);
+        Self::new(Assigner::Tumbling { size }, timestamp)
+    }
+
+    /// Fixed size windows starting every `slide`, an element is in every window covering it
+    pub fn sliding(size: Duration, slide: Duration, timestamp: F) -> Self {
+        let size = (size.as_millis() as u64).max(1);
+        let slide = (slide.as_millis() as u64).max(1);
+        Self::new(Assigner::Sliding { size, slide }, timestamp)
+    }
+
+    /// Windows of activity that close after no element arrived for `gap`
+    pub fn session(gap: Duration, timestamp: F) -> Self {
+        let gap = (gap.as_millis() as u64).max(1);
+        Self::new(Assigner::Session { gap }, timestamp)
+    }
+
+    /// How far behind the largest timestamp seen the watermark is held, giving out of order
+    /// elements time to arrive before their window fires. Defaults to zero.
+    pub fn allowed_lateness(mut self, lateness: Duration) -> Self {
+        self.allowed_lateness = lateness.as_millis() as u64;
+        self
+    }
+
+    pub fn late_data(mut self, late_data: LateData) -> Self {
+        self.late_data = late_data;
+        self
+    }
+
+    fn is_fired(&self, end: u64) -> bool {
+        self.watermark.map(|wm| end <= wm).unwrap_or(false)
+    }
+
+    fn late(&self, window: (u64, u64), element: T, out: &mut Emitter<Window<T>>) {
+        match self.late_data {
+            LateData::Drop => debug!(
+                "dropping late element for window [{}, {})",
+                window.0, window.1
+            ),
+            LateData::EmitLatePane => out.emit(Window {
+                start: window.0,
+                end: window.1,
+                late: true,
+                elements: vec![element],
+            }),
+        }
+    }
+
+    /// Adds an element to a session, merging every session it overlaps
+    fn merge_session(&mut self, window: (u64, u64), element: T) {
+        let overlapping: Vec<(u64, u64)> = self
+            .panes
+            .range(..(window.1, 0))
+            .map(|(w, _)| *w)
+            .filter(|(_, end)| *end > window.0)
+            .collect();
+
+        let mut merged = window;
+        let mut elements = Vec::new();
+
+        for w in overlapping {
+            merged = (merged.0.min(w.0), merged.1.max(w.1));
+            elements.extend(self.panes.remove(&w).unwrap_or_default());
+        }
+
+        elements.push(element);
+        self.panes.insert(merged, elements);
+    }
+
+    /// Emits every window the watermark has passed, in order of their start
+    fn fire(&mut self, out: &mut Emitter<Window<T>>) {
+        let fired: Vec<(u64, u64)> = self
+            .panes
+            .keys()
+            .filter(|(_, end)| self.is_fired(*end))
+            .copied()
+            .collect();
+
+        for (start, end) in fired {
+            if let Some(elements) = self.panes.remove(&(start, end)) {
+                out.emit(Window {
+                    start,
+                    end,
+                    late: false,
+                    elements,
+                });
+            }
+        }
+    }
+}
+
+impl<T, F> StreamOperator for WindowXform<T, F>
+where
+    T: MxlData,
+    F: Fn(&T) -> u64,
+{
+    type Input = T;
+    type Output = Window<T>;
+
+    fn on_element(&mut self, element: T, out: &mut Emitter<Window<T>>) -> Result<()> {
+        let ts = (self.timestamp)(&element);
+
+        let windows = self.assigner.assign(ts);
+
+        for window in windows {
+            if self.is_fired(window.1) {
+                self.late(window, element.clone(), out);
+            } else if let Assigner::Session { .. } = self.assigner {
+                self.merge_session(window, element.clone());
+            } else {
+                self.panes.entry(window).or_default().push(element.clone());
+            }
+        }
+
+        let watermark = ts.saturating_sub(self.allowed_lateness);
+        if self.watermark.map(|wm| watermark > wm).unwrap_or(true) {
+            self.watermark = Some(watermark);
+            self.fire(out);
+        }
+
+        Ok(())
+    }
+
+    fn on_end_of_input(&mut self, _port: u32, out: &mut Emitter<Window<T>>) -> Result<()> {
+        self.watermark = Some(u64::MAX);
+        self.fire(out);
+        Ok(())
+    }
+
+    fn label(&self) -> Option<String> {
+        let label = match self.assigner {
+            Assigner::Tumbling { size } => format!("TumblingWindow[{}ms]", size),
+            Assigner::Sliding { size, slide } => format!("SlidingWindow[{}ms/{}ms]", size, slide),
+            Assigner::Session { gap } => format!("SessionWindow[{}ms]", gap),
+        };
+
+        Some(label)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{drain, push, tick, Frames};
+    use crate::{transform, MxlGraph, OperatorNode};
+
+    #[test]
+    fn tumbling_window_fires_on_watermark_and_drops_late_data() {
+        let mut g = MxlGraph::new();
+        let window = g.transform(OperatorNode::new(transform::tumbling_window(
+            Duration::from_millis(10),
+            |ts: &u32| *ts as u64,
+        )));
+
+        let input: Frames = Default::default();
+        let output: Frames = Default::default();
+
+        // 3 arrives after [0, 10) fired
+        push(&input, vec![1u32, 5, 12, 3, 25]);
+        tick(&mut g, window.id(), &[(0, &input)], &output, 10);
+
+        let windows: Vec<_> = drain::<Window<u32>>(&output)
+            .into_iter()
+            .map(|frame| match frame {
+                Frame::Data(w) => format!("[{}, {}) {:?}", w.start, w.end, w.elements),
+                other => format!("{:?}", other),
+            })
+            .collect();
+
+        assert_eq!(
+            windows,
+            vec!["[0, 10) [1, 5]", "[10, 20) [12]", "[20, 30) [25]", "End"]
+        );
+    }
+
+    #[test]
+    fn truncated_window_frame_decodes_to_error() {
+        let window = Window {
+            start: 0,
+            end: 10,
+            late: false,
+            elements: vec![1u32],
+        };
+
+        let bytes = match window.into_buffer_frame().unwrap() {
+            Frame::Data(bytes) => bytes,
+            other => panic!("expected data, got {:?}", other),
+        };
+
+        assert!(matches!(
+            Window::<u32>::from_buffer_frame(Frame::Data(bytes.clone())),
+            Frame::Data(Window { start: 0, end: 10, .. })
+        ));
+        assert!(matches!(
+            Window::<u32>::from_buffer_frame(Frame::Data(bytes.slice(..16))),
+            Frame::Error
+        ));
+    }
+}
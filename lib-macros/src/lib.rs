@@ -1,71 +1,184 @@
+mod node;
+mod schema;
+
 use proc_macro2::TokenStream;
-use quote::{quote, ToTokens};
-use syn::ItemFn;
+use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
+use syn::{ItemFn, Path};
 
 #[proc_macro_attribute]
 pub fn builder(
-    _attr: proc_macro::TokenStream,
+    attr: proc_macro::TokenStream,
     item: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
     let item2: proc_macro2::TokenStream = item.clone().into();
 
+    let krate = match parse_crate_path(attr.into()) {
+        Ok(krate) => krate,
+        Err(e) => return token_stream_with_error(item2, e).into(),
+    };
+
     match syn::parse2::<ItemFn>(item2.clone()) {
         Ok(it) => {
-            // println!("it: name = {}", it.sig.ident.to_string());
-
-            if it.sig.ident.to_string() != "main" {
-                let msg = "valence::main must apply to a function named main";
+            if it.sig.ident != "main" {
+                let msg = "#[builder] must be applied to a function named main";
                 let error = syn::Error::new_spanned(&it.sig.ident, msg);
                 return token_stream_with_error(item2, error).into();
             }
 
-            match it.sig.output {
-                syn::ReturnType::Type(_, ty) if is_mxlgraph(ty.as_ref()) => {}
-                _ => {
-                    let msg = "mixlayer::main must return a Result<MxlGraph>";
-                    let error = syn::Error::new_spanned(&it.sig.ident, msg);
+            let ret_ty = match &it.sig.output {
+                syn::ReturnType::Type(_, ty) => ty.as_ref(),
+                syn::ReturnType::Default => {
+                    let error = syn::Error::new_spanned(&it.sig.ident, RETURN_TYPE_MSG);
                     return token_stream_with_error(item2, error).into();
                 }
+            };
+
+            if let Err(error) = check_return_type(ret_ty) {
+                return token_stream_with_error(item2, error).into();
             }
 
-            //TODO just unwrapping Result<VGraph> for now, but return actual error to user in future
+            // anything that got past `check_return_type` without being spelled as a Result
+            // (e.g. a type alias) is checked by the compiler here, spanned to the return type
+            let result = quote_spanned! {ret_ty.span()=>
+                let result: ::core::result::Result<#krate::MxlGraph, _> = main();
+            };
+
+            // the host receives an `InitResult` holding either the graph pointer or the error chain
             let tokens = quote! {
                 #[no_mangle]
-                extern "C" fn _valence_app_init() -> *mut MxlGraph {
+                extern "C" fn _valence_app_init() -> *mut #krate::ByteBuffer {
                     #item2
 
-                    let g: MxlGraph = main().unwrap();
-                    Box::into_raw(Box::new(g))
+                    #result
+                    #krate::app_init_result(result)
                 }
             };
 
-            // println!("output: \"{}\"", tokens.to_string());
-
-            return tokens.into();
+            tokens.into()
         }
-        Err(e) => return token_stream_with_error(item2, e).into(),
-    };
+        Err(e) => token_stream_with_error(item2, e).into(),
+    }
+}
+
+/// Turns a plain function or an `impl` block with `on_data`/`on_end` hooks into a
+/// `StreamOperator`.
+///
+/// A function `fn clean(doc: I) -> R` is kept as is and gets a `Clean` operator struct calling it,
+/// `Clean::node()` returns the operator wrapped in an `OperatorNode`. On an `impl` block, `on_data(&mut self, input: I) -> R` is called
+/// for every element and the optional `on_end(&mut self) -> R` once the input has finished; add
+/// it to a graph with `MxlNodeRef::operator`.
+///
+/// `R` may be `O`, `Option<O>`, `Result<O>` or `Result<Option<O>>`. `None` emits nothing and an
+/// error is sent downstream as `Frame::Error` before being returned from `tick`.
+///
+/// Accepts `crate = "..."`, `name = "..."` (the generated struct) and `label = "..."`.
+#[proc_macro_attribute]
+pub fn node(
+    attr: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let item2: proc_macro2::TokenStream = item.into();
+
+    match node::expand(attr.into(), item2.clone()) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => token_stream_with_error(item2, e).into(),
+    }
 }
 
+/// Implements `mixdb::MixDbSchema` for a struct, describing a collection schema with its fields.
+///
+/// Field types are inferred from strings, integers, floats and `bool`, `Option` fields are
+/// optional and `Vec` fields repeated. `#[mixdb(keyword)]`, `text`, `integer`, `float`, `date`,
+/// `boolean` or `nested` sets the type, `#[mixdb(rename = "...")]` the name and
+/// `#[mixdb(skip)]` leaves a field out. Field names follow `#[serde(rename)]` and
+/// `#[serde(rename_all)]` unless renamed with `#[mixdb]`. Accepts `#[mixdb(crate = "...")]` on
+/// the struct.
+#[proc_macro_derive(MixDbSchema, attributes(mixdb))]
+pub fn derive_mixdb_schema(item: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let input = syn::parse_macro_input!(item as syn::DeriveInput);
+
+    match schema::expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.into_compile_error().into(),
+    }
+}
+
+const RETURN_TYPE_MSG: &str = "#[builder] function must return a Result<MxlGraph>";
+
 fn token_stream_with_error(mut tokens: TokenStream, error: syn::Error) -> TokenStream {
     tokens.extend(error.into_compile_error());
     tokens
 }
 
-fn is_mxlgraph(ty: &syn::Type) -> bool {
-    let str_type = ty.to_token_stream().to_string();
-
-    // dbg!(&str_type);
-
-    //TODO this will break if the valence crate is aliased, tokio gets around this by letting you
-    // define the alias in the macro args
-    match str_type.as_str() {
-        "Result < MxlGraph >" => {}
-        "Result < valence :: MxlGraph >" => {}
-        "mixlayer :: Result < mixlayer :: MxlGraph >" => {}
-        "mixlayer :: Result < MxlGraph >" => {}
-        _ => return false,
+/// Parses the macro arguments, returning the path generated code uses to refer to the mixlayer
+/// crate. Defaults to `mixlayer`, but can be overridden with `crate = "..."` if the crate is
+/// renamed or re-exported, the same way tokio handles it.
+fn parse_crate_path(attr: TokenStream) -> syn::Result<Path> {
+    let mut krate: Path = syn::parse_quote!(mixlayer);
+
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("crate") {
+            let lit: syn::LitStr = meta.value()?.parse()?;
+            krate = lit.parse().map_err(|_| {
+                syn::Error::new_spanned(&lit, "`crate` must be a path, e.g. `crate = \"mixlayer\"`")
+            })?;
+            Ok(())
+        } else {
+            Err(meta.error("unknown #[builder] argument, expected `crate = \"...\"`"))
+        }
+    });
+
+    syn::parse::Parser::parse2(parser, attr)?;
+
+    Ok(krate)
+}
+
+/// Rejects return types that are spelled as a `Result` of something other than `MxlGraph`.
+/// Any path ending in `Result` is accepted as a result type and any path ending in `MxlGraph` as
+/// the graph, so renamed crates and `anyhow::Result` work. Types that aren't spelled as a
+/// `Result` at all, like aliases, are left for the compiler to check.
+fn check_return_type(ty: &syn::Type) -> syn::Result<()> {
+    let path = match ty {
+        syn::Type::Path(ty) if ty.qself.is_none() => &ty.path,
+        syn::Type::Paren(ty) => return check_return_type(&ty.elem),
+        syn::Type::Group(ty) => return check_return_type(&ty.elem),
+        other => return Err(syn::Error::new_spanned(other, RETURN_TYPE_MSG)),
     };
 
-    true
+    let last = path.segments.last().unwrap();
+
+    if last.ident != "Result" {
+        return Ok(());
+    }
+
+    let ok_ty = match &last.arguments {
+        syn::PathArguments::AngleBracketed(args) => args.args.iter().find_map(|arg| match arg {
+            syn::GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        }),
+        // a bare `Result` is an alias the compiler has to resolve
+        syn::PathArguments::None => return Ok(()),
+        _ => None,
+    };
+
+    match ok_ty {
+        Some(ok_ty) if is_mxlgraph(ok_ty) => Ok(()),
+        Some(ok_ty) => Err(syn::Error::new_spanned(ok_ty, RETURN_TYPE_MSG)),
+        None => Err(syn::Error::new_spanned(ty, RETURN_TYPE_MSG)),
+    }
+}
+
+fn is_mxlgraph(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Path(ty) => ty
+            .path
+            .segments
+            .last()
+            .map(|seg| seg.ident == "MxlGraph" && seg.arguments.is_none())
+            .unwrap_or(false),
+        syn::Type::Paren(ty) => is_mxlgraph(&ty.elem),
+        syn::Type::Group(ty) => is_mxlgraph(&ty.elem),
+        _ => false,
+    }
 }
@@ -0,0 +1,393 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::{FnArg, Ident, ImplItem, ImplItemFn, ItemFn, ItemImpl, Path, ReturnType, Type};
+
+/// Arguments accepted by `#[node(...)]`
+struct NodeArgs {
+    krate: Path,
+    name: Option<Ident>,
+    label: Option<String>,
+}
+
+impl NodeArgs {
+    fn parse(attr: TokenStream) -> syn::Result<Self> {
+        let mut args = NodeArgs {
+            krate: syn::parse_quote!(mixlayer),
+            name: None,
+            label: None,
+        };
+
+        let parser = syn::meta::parser(|meta| {
+            if meta.path.is_ident("crate") {
+                let lit: syn::LitStr = meta.value()?.parse()?;
+                args.krate = lit.parse().map_err(|_| {
+                    syn::Error::new_spanned(
+                        &lit,
+                        "`crate` must be a path, e.g. `crate = \"mixlayer\"`",
+                    )
+                })?;
+            } else if meta.path.is_ident("name") {
+                let lit: syn::LitStr = meta.value()?.parse()?;
+                args.name = Some(lit.parse()?);
+            } else if meta.path.is_ident("label") {
+                let lit: syn::LitStr = meta.value()?.parse()?;
+                args.label = Some(lit.value());
+            } else {
+                return Err(meta.error(
+                    "unknown #[node] argument, expected one of `crate`, `name` or `label`",
+                ));
+            }
+
+            Ok(())
+        });
+
+        syn::parse::Parser::parse2(parser, attr)?;
+
+        Ok(args)
+    }
+}
+
+/// How the value returned by a node function is turned into output frames
+struct OutputShape {
+    /// returns a `Result`, errors are routed downstream as `Frame::Error` and returned from tick
+    fallible: bool,
+    /// returns an `Option`, `None` emits nothing
+    optional: bool,
+    /// the emitted type, `None` if the function returns `()`
+    ty: Option<Type>,
+}
+
+impl OutputShape {
+    fn from_return_type(ret: &ReturnType) -> Self {
+        let ty = match ret {
+            ReturnType::Default => {
+                return Self {
+                    fallible: false,
+                    optional: false,
+                    ty: None,
+                }
+            }
+            ReturnType::Type(_, ty) => ty.as_ref(),
+        };
+
+        let (fallible, ty) = match generic_arg_of(ty, "Result") {
+            Some(inner) => (true, inner),
+            None => (false, ty),
+        };
+
+        let (optional, ty) = match generic_arg_of(ty, "Option") {
+            Some(inner) => (true, inner),
+            None => (false, ty),
+        };
+
+        let ty = match ty {
+            Type::Tuple(tuple) if tuple.elems.is_empty() => None,
+            other => Some(other.clone()),
+        };
+
+        Self {
+            fallible,
+            optional,
+            ty,
+        }
+    }
+
+    /// Generates statements that emit `value`, an expression of the function's return type
+    fn emit(&self, krate: &Path, value: TokenStream, label: &str) -> TokenStream {
+        let unwrap = if self.fallible {
+            let msg = format!("error in node {}", label);
+            quote! {
+                let value = match #krate::graph::Context::context(value, #msg) {
+                    Ok(value) => value,
+                    Err(err) => {
+                        out.error();
+                        return Err(err);
+                    }
+                };
+            }
+        } else {
+            quote! {}
+        };
+
+        let emit = match (&self.ty, self.optional) {
+            (None, _) => quote! { let () = value; },
+            (Some(_), true) => quote! {
+                if let Some(value) = value {
+                    out.emit(value);
+                }
+            },
+            (Some(_), false) => quote! { out.emit(value); },
+        };
+
+        quote! {
+            let value = #value;
+            #unwrap
+            #emit
+        }
+    }
+}
+
+/// Returns the first type argument of `ty` if it's a path ending in `name`, e.g. `T` for
+/// `anyhow::Result<T>` when `name` is `Result`
+fn generic_arg_of<'a>(ty: &'a Type, name: &str) -> Option<&'a Type> {
+    let path = match ty {
+        Type::Path(ty) if ty.qself.is_none() => &ty.path,
+        _ => return None,
+    };
+
+    let last = path.segments.last()?;
+
+    if last.ident != name {
+        return None;
+    }
+
+    match &last.arguments {
+        syn::PathArguments::AngleBracketed(args) => args.args.iter().find_map(|arg| match arg {
+            syn::GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
+pub fn expand(attr: TokenStream, item: TokenStream) -> syn::Result<TokenStream> {
+    let args = NodeArgs::parse(attr)?;
+
+    if let Ok(item_fn) = syn::parse2::<ItemFn>(item.clone()) {
+        return expand_fn(args, item_fn);
+    }
+
+    if let Ok(item_impl) = syn::parse2::<ItemImpl>(item.clone()) {
+        return expand_impl(args, item_impl);
+    }
+
+    Err(syn::Error::new(
+        Span::call_site(),
+        "#[node] must be applied to a function or an impl block",
+    ))
+}
+
+fn expand_fn(args: NodeArgs, item: ItemFn) -> syn::Result<TokenStream> {
+    let sig = &item.sig;
+
+    if !sig.generics.params.is_empty() {
+        let msg = "#[node] functions cannot be generic";
+        return Err(syn::Error::new_spanned(&sig.generics, msg));
+    }
+
+    if let Some(asyncness) = &sig.asyncness {
+        return Err(syn::Error::new_spanned(
+            asyncness,
+            "#[node] functions cannot be async",
+        ));
+    }
+
+    let input_ty = match single_input(sig.inputs.iter())? {
+        Some(ty) => ty,
+        None => {
+            let msg = "#[node] functions take exactly one argument, the input element";
+            return Err(syn::Error::new_spanned(&sig.ident, msg));
+        }
+    };
+
+    let shape = OutputShape::from_return_type(&sig.output);
+
+    let output_ty = match &shape.ty {
+        Some(ty) => ty,
+        None => {
+            let msg = "#[node] functions must return the output element";
+            return Err(syn::Error::new_spanned(&sig.ident, msg));
+        }
+    };
+
+    let krate = &args.krate;
+    let vis = &item.vis;
+    let fn_name = &sig.ident;
+    let struct_name = args
+        .name
+        .clone()
+        .unwrap_or_else(|| format_ident!("{}", upper_camel_case(&fn_name.to_string())));
+    let label = args.label.clone().unwrap_or_else(|| fn_name.to_string());
+
+    let docs = item.attrs.iter().filter(|attr| attr.path().is_ident("doc"));
+    let emit = shape.emit(krate, quote! { #fn_name(input) }, &label);
+
+    // the function is kept as written so it can still be called and tested on its own
+    Ok(quote! {
+        #item
+
+        #(#docs)*
+        #vis struct #struct_name;
+
+        impl #struct_name {
+            #[doc = concat!("Creates a node running `", stringify!(#fn_name), "` on every element")]
+            #vis fn node() -> #krate::graph::OperatorNode<#struct_name> {
+                #krate::graph::OperatorNode::new(#struct_name)
+            }
+        }
+
+        impl #krate::graph::StreamOperator for #struct_name {
+            type Input = #input_ty;
+            type Output = #output_ty;
+
+            fn on_element(
+                &mut self,
+                input: Self::Input,
+                out: &mut #krate::graph::Emitter<Self::Output>,
+            ) -> #krate::Result<()> {
+                #emit
+                Ok(())
+            }
+
+            fn label(&self) -> Option<String> {
+                Some(#label.to_owned())
+            }
+        }
+    })
+}
+
+fn expand_impl(args: NodeArgs, item: ItemImpl) -> syn::Result<TokenStream> {
+    if let Some((_, trait_path, _)) = &item.trait_ {
+        let msg = "#[node] must be applied to an inherent impl block";
+        return Err(syn::Error::new_spanned(trait_path, msg));
+    }
+
+    let find_fn = |name: &str| {
+        item.items.iter().find_map(|it| match it {
+            ImplItem::Fn(f) if f.sig.ident == name => Some(f),
+            _ => None,
+        })
+    };
+
+    let on_data = match find_fn("on_data") {
+        Some(f) => f,
+        None => {
+            let msg = "#[node] impl blocks must define `fn on_data(&mut self, input: I)`";
+            return Err(syn::Error::new_spanned(&item.self_ty, msg));
+        }
+    };
+
+    let on_end = find_fn("on_end");
+
+    check_receiver(on_data)?;
+
+    let input_ty = match single_input(on_data.sig.inputs.iter().skip(1))? {
+        Some(ty) => ty,
+        None => {
+            let msg = "`on_data` takes exactly one argument besides `&mut self`, the input element";
+            return Err(syn::Error::new_spanned(&on_data.sig.ident, msg));
+        }
+    };
+
+    let data_shape = OutputShape::from_return_type(&on_data.sig.output);
+
+    let output_ty = match &data_shape.ty {
+        Some(ty) => ty,
+        None => {
+            let msg = "`on_data` must return the output element";
+            return Err(syn::Error::new_spanned(&on_data.sig.ident, msg));
+        }
+    };
+
+    let krate = &args.krate;
+    let self_ty = &item.self_ty;
+    let (impl_generics, _, where_clause) = item.generics.split_for_impl();
+
+    let label = args.label.clone().unwrap_or_else(|| {
+        let ty = quote!(#self_ty).to_string();
+        ty.split('<').next().unwrap_or_default().trim().to_owned()
+    });
+
+    let emit_data = data_shape.emit(krate, quote! { self.on_data(input) }, &label);
+
+    let emit_end = match on_end {
+        Some(on_end) => {
+            check_receiver(on_end)?;
+
+            if on_end.sig.inputs.len() != 1 {
+                let msg = "`on_end` takes no arguments besides `&mut self`";
+                return Err(syn::Error::new_spanned(&on_end.sig.inputs, msg));
+            }
+
+            let end_shape = OutputShape::from_return_type(&on_end.sig.output);
+            end_shape.emit(krate, quote! { self.on_end() }, &label)
+        }
+        None => quote! {},
+    };
+
+    Ok(quote! {
+        #item
+
+        impl #impl_generics #krate::graph::StreamOperator for #self_ty #where_clause {
+            type Input = #input_ty;
+            type Output = #output_ty;
+
+            fn on_element(
+                &mut self,
+                input: Self::Input,
+                out: &mut #krate::graph::Emitter<Self::Output>,
+            ) -> #krate::Result<()> {
+                #emit_data
+                Ok(())
+            }
+
+            fn on_end_of_input(
+                &mut self,
+                _port: u32,
+                out: &mut #krate::graph::Emitter<Self::Output>,
+            ) -> #krate::Result<()> {
+                #emit_end
+                Ok(())
+            }
+
+            fn label(&self) -> Option<String> {
+                Some(#label.to_owned())
+            }
+        }
+    })
+}
+
+fn check_receiver(f: &ImplItemFn) -> syn::Result<()> {
+    match f.sig.inputs.first() {
+        Some(FnArg::Receiver(recv)) if recv.reference.is_some() && recv.mutability.is_some() => {
+            Ok(())
+        }
+        _ => {
+            let msg = format!("`{}` must take `&mut self`", f.sig.ident);
+            Err(syn::Error::new_spanned(&f.sig.ident, msg))
+        }
+    }
+}
+
+/// Returns the type of the only typed argument, `None` if there isn't exactly one
+fn single_input<'a>(mut inputs: impl Iterator<Item = &'a FnArg>) -> syn::Result<Option<Type>> {
+    let first = match inputs.next() {
+        Some(FnArg::Typed(arg)) => arg.ty.as_ref().clone(),
+        Some(FnArg::Receiver(recv)) => {
+            return Err(syn::Error::new_spanned(
+                recv,
+                "#[node] functions cannot take self",
+            ))
+        }
+        None => return Ok(None),
+    };
+
+    if inputs.next().is_some() {
+        return Ok(None);
+    }
+
+    Ok(Some(first))
+}
+
+fn upper_camel_case(name: &str) -> String {
+    name.split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,377 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, GenericArgument, Path, PathArguments, Type};
+
+/// Field type given with an attribute, or inferred from the Rust type
+enum Kind {
+    Text,
+    Keyword,
+    Integer,
+    Float,
+    Date,
+    Boolean,
+    Nested,
+}
+
+impl Kind {
+    fn from_ident(ident: &syn::Ident) -> Option<Self> {
+        let kind = match ident.to_string().as_str() {
+            "text" => Kind::Text,
+            "keyword" => Kind::Keyword,
+            "integer" => Kind::Integer,
+            "float" => Kind::Float,
+            "date" => Kind::Date,
+            "boolean" => Kind::Boolean,
+            "nested" => Kind::Nested,
+            _ => return None,
+        };
+
+        Some(kind)
+    }
+
+    fn infer(ty: &Type) -> Option<Self> {
+        let ident = last_segment(ty)?.ident.to_string();
+
+        let kind = match ident.as_str() {
+            "String" | "str" => Kind::Text,
+            "i8" | "i16" | "i32" | "i64" | "isize" | "u8" | "u16" | "u32" | "u64" | "usize" => {
+                Kind::Integer
+            }
+            "f32" | "f64" => Kind::Float,
+            "bool" => Kind::Boolean,
+            _ => return None,
+        };
+
+        Some(kind)
+    }
+}
+
+/// Arguments accepted by `#[mixdb(...)]` on a field
+#[derive(Default)]
+struct FieldArgs {
+    kind: Option<Kind>,
+    rename: Option<String>,
+    skip: bool,
+}
+
+impl FieldArgs {
+    fn parse(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+        let mut args = FieldArgs::default();
+
+        for attr in attrs.iter().filter(|a| a.path().is_ident("mixdb")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    let lit: syn::LitStr = meta.value()?.parse()?;
+                    args.rename = Some(lit.value());
+                } else if meta.path.is_ident("skip") {
+                    args.skip = true;
+                } else if let Some(kind) = meta.path.get_ident().and_then(Kind::from_ident) {
+                    if args.kind.is_some() {
+                        return Err(meta.error("field type given more than once"));
+                    }
+                    args.kind = Some(kind);
+                } else {
+                    return Err(meta.error(
+                        "unknown #[mixdb] argument, expected a field type (`text`, `keyword`, \
+                         `integer`, `float`, `date`, `boolean`, `nested`), `rename` or `skip`",
+                    ));
+                }
+
+                Ok(())
+            })?;
+        }
+
+        Ok(args)
+    }
+}
+
+/// Case conversion of `#[serde(rename_all = "...")]`, applied to field names as serde does
+enum RenameRule {
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    ScreamingSnake,
+    Kebab,
+    ScreamingKebab,
+}
+
+impl RenameRule {
+    fn from_lit(lit: &syn::LitStr) -> syn::Result<Self> {
+        let rule = match lit.value().as_str() {
+            "lowercase" | "snake_case" => RenameRule::Lower,
+            "UPPERCASE" => RenameRule::Upper,
+            "PascalCase" => RenameRule::Pascal,
+            "camelCase" => RenameRule::Camel,
+            "SCREAMING_SNAKE_CASE" => RenameRule::ScreamingSnake,
+            "kebab-case" => RenameRule::Kebab,
+            "SCREAMING-KEBAB-CASE" => RenameRule::ScreamingKebab,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    lit,
+                    "unknown serde rename_all rule",
+                ))
+            }
+        };
+
+        Ok(rule)
+    }
+
+    fn apply(&self, field: &str) -> String {
+        match self {
+            RenameRule::Lower => field.to_owned(),
+            RenameRule::Upper | RenameRule::ScreamingSnake => field.to_ascii_uppercase(),
+            RenameRule::Pascal => field
+                .split('_')
+                .map(|word| {
+                    let mut chars = word.chars();
+                    match chars.next() {
+                        Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                        None => String::new(),
+                    }
+                })
+                .collect(),
+            RenameRule::Camel => {
+                let pascal = RenameRule::Pascal.apply(field);
+                let mut chars = pascal.chars();
+                match chars.next() {
+                    Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+                    None => pascal,
+                }
+            }
+            RenameRule::Kebab => field.replace('_', "-"),
+            RenameRule::ScreamingKebab => field.to_ascii_uppercase().replace('_', "-"),
+        }
+    }
+}
+
+/// The `rename`, `rename_all` and `skip` arguments of `#[serde(...)]`, so the schema names fields
+/// the way serde serializes them and leaves out the ones it doesn't write. `flatten` is an error,
+/// other serde arguments are skipped.
+#[derive(Default)]
+struct SerdeArgs {
+    rename: Option<String>,
+    rename_all: Option<RenameRule>,
+    skip: bool,
+}
+
+impl SerdeArgs {
+    fn parse(attrs: &[syn::Attribute]) -> syn::Result<Self> {
+        let mut args = SerdeArgs::default();
+
+        for attr in attrs.iter().filter(|a| a.path().is_ident("serde")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    if let Some(lit) = serialize_name(&meta)? {
+                        args.rename = Some(lit.value());
+                    }
+                } else if meta.path.is_ident("rename_all") {
+                    if let Some(lit) = serialize_name(&meta)? {
+                        args.rename_all = Some(RenameRule::from_lit(&lit)?);
+                    }
+                } else if meta.path.is_ident("skip") || meta.path.is_ident("skip_serializing") {
+                    args.skip = true;
+                } else if meta.path.is_ident("flatten") {
+                    return Err(meta.error(
+                        "#[derive(MixDbSchema)] doesn't support #[serde(flatten)], \
+                         declare the fields on this struct or use #[mixdb(nested)]",
+                    ));
+                } else if meta.input.peek(syn::Token![=]) {
+                    meta.value()?.parse::<syn::Expr>()?;
+                } else if meta.input.peek(syn::token::Paren) {
+                    let content;
+                    syn::parenthesized!(content in meta.input);
+                    content.parse::<TokenStream>()?;
+                }
+
+                Ok(())
+            })?;
+        }
+
+        Ok(args)
+    }
+}
+
+/// Reads `rename = "..."` or the serialize half of `rename(serialize = "...", ...)`
+fn serialize_name(meta: &syn::meta::ParseNestedMeta) -> syn::Result<Option<syn::LitStr>> {
+    if meta.input.peek(syn::Token![=]) {
+        return Ok(Some(meta.value()?.parse()?));
+    }
+
+    let mut name = None;
+
+    meta.parse_nested_meta(|nested| {
+        let lit: syn::LitStr = nested.value()?.parse()?;
+
+        if nested.path.is_ident("serialize") {
+            name = Some(lit);
+        }
+
+        Ok(())
+    })?;
+
+    Ok(name)
+}
+
+/// Parses `#[mixdb(crate = "...")]` on the type, see `parse_crate_path` in lib.rs
+fn parse_crate_path(attrs: &[syn::Attribute]) -> syn::Result<Path> {
+    let mut krate: Path = syn::parse_quote!(mixlayer);
+
+    for attr in attrs.iter().filter(|a| a.path().is_ident("mixdb")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("crate") {
+                let lit: syn::LitStr = meta.value()?.parse()?;
+                krate = lit.parse().map_err(|_| {
+                    syn::Error::new_spanned(
+                        &lit,
+                        "`crate` must be a path, e.g. `crate = \"mixlayer\"`",
+                    )
+                })?;
+                Ok(())
+            } else {
+                Err(meta.error("unknown #[mixdb] argument, expected `crate = \"...\"`"))
+            }
+        })?;
+    }
+
+    Ok(krate)
+}
+
+fn last_segment(ty: &Type) -> Option<&syn::PathSegment> {
+    match ty {
+        Type::Path(ty) if ty.qself.is_none() => ty.path.segments.last(),
+        Type::Reference(ty) => last_segment(&ty.elem),
+        Type::Group(ty) => last_segment(&ty.elem),
+        Type::Paren(ty) => last_segment(&ty.elem),
+        _ => None,
+    }
+}
+
+/// Returns the `T` of `Wrapper<T>` if `ty` is spelled as `wrapper`
+fn unwrap_generic<'a>(ty: &'a Type, wrapper: &str) -> Option<&'a Type> {
+    let segment = last_segment(ty)?;
+
+    if segment.ident != wrapper {
+        return None;
+    }
+
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) => args.args.iter().find_map(|arg| match arg {
+            GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
+pub(crate) fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    let krate = parse_crate_path(&input.attrs)?;
+    let rename_all = SerdeArgs::parse(&input.attrs)?.rename_all;
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "#[derive(MixDbSchema)] needs a struct with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "#[derive(MixDbSchema)] can only be used on structs",
+            ))
+        }
+    };
+
+    let mut schema_fields = Vec::new();
+
+    for field in fields {
+        let args = FieldArgs::parse(&field.attrs)?;
+        let serde_args = SerdeArgs::parse(&field.attrs)?;
+
+        if args.skip || serde_args.skip {
+            continue;
+        }
+
+        let ident = field.ident.as_ref().unwrap();
+        let serde_rename = serde_args.rename;
+
+        // same precedence as serde, with #[mixdb(rename)] overriding it
+        let name = match (args.rename, serde_rename, &rename_all) {
+            (Some(name), _, _) | (None, Some(name), _) => name,
+            (None, None, rule) => {
+                let name = ident.to_string().trim_start_matches("r#").to_owned();
+                match rule {
+                    Some(rule) => rule.apply(&name),
+                    None => name,
+                }
+            }
+        };
+
+        let mut ty = &field.ty;
+
+        let required = match unwrap_generic(ty, "Option") {
+            Some(inner) => {
+                ty = inner;
+                false
+            }
+            None => true,
+        };
+
+        let repeated = match unwrap_generic(ty, "Vec") {
+            Some(inner) => {
+                ty = inner;
+                true
+            }
+            None => false,
+        };
+
+        let kind = match args.kind.or_else(|| Kind::infer(ty)) {
+            Some(kind) => kind,
+            None => {
+                return Err(syn::Error::new_spanned(
+                    &field.ty,
+                    "can't infer the mixdb field type, add #[mixdb(keyword)], #[mixdb(date)], \
+                     #[mixdb(nested)] or another field type",
+                ))
+            }
+        };
+
+        let field_type = match kind {
+            Kind::Text => quote!(#krate::mixdb::FieldType::Text),
+            Kind::Keyword => quote!(#krate::mixdb::FieldType::Keyword),
+            Kind::Integer => quote!(#krate::mixdb::FieldType::Integer),
+            Kind::Float => quote!(#krate::mixdb::FieldType::Float),
+            Kind::Date => quote!(#krate::mixdb::FieldType::Date),
+            Kind::Boolean => quote!(#krate::mixdb::FieldType::Boolean),
+            Kind::Nested => quote! {
+                #krate::mixdb::FieldType::Nested(
+                    <#ty as #krate::mixdb::MixDbSchema>::schema().fields
+                )
+            },
+        };
+
+        schema_fields.push(quote! {
+            #krate::mixdb::SchemaField {
+                name: #name.to_owned(),
+                field_type: #field_type,
+                required: #required,
+                repeated: #repeated,
+            }
+        });
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics #krate::mixdb::MixDbSchema for #ident #ty_generics #where_clause {
+            fn schema() -> #krate::mixdb::Schema {
+                #krate::mixdb::Schema::new(::std::vec![#(#schema_fields),*])
+            }
+        }
+    })
+}
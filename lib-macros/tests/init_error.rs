@@ -0,0 +1,24 @@
+use anyhow::{anyhow, Context};
+use mixlayer::{MxlGraph, Result};
+use mixlayer_macros::builder;
+use mixlayer_runtime_ffi::prost::Message;
+use mixlayer_runtime_ffi::protos::{init_result, InitResult};
+
+#[test]
+fn init_returns_error_chain() {
+    #[builder]
+    fn main() -> Result<MxlGraph> {
+        Err(anyhow!("missing.txt not found")).context("error opening source")
+    }
+
+    let buf = unsafe { Box::from_raw(_valence_app_init()) };
+    let init_result = InitResult::decode(buf.into_bytes()).unwrap();
+
+    match init_result.result {
+        Some(init_result::Result::Error(err)) => {
+            assert_eq!(err.message, "error opening source");
+            assert_eq!(err.chain, vec!["error opening source", "missing.txt not found"]);
+        }
+        other => panic!("expected init error, got {:?}", other),
+    }
+}
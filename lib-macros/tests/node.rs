@@ -0,0 +1,142 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+
+use anyhow::anyhow;
+use mixlayer::graph::{MxlData, MxlNode, MxlNodeCtx};
+use mixlayer::{Frame, Input, InputChannel, OperatorNode, Output, OutputChannel, Result};
+use mixlayer_macros::node;
+
+type Frames = Rc<RefCell<VecDeque<Frame<bytes::Bytes>>>>;
+
+struct VecChannel(Frames);
+
+impl InputChannel for VecChannel {
+    fn finished(&self) -> bool {
+        self.0.borrow().is_empty()
+    }
+
+    fn finished_writing(&self) -> bool {
+        true
+    }
+
+    fn recv(&self) -> Option<Frame<bytes::Bytes>> {
+        self.0.borrow_mut().pop_front()
+    }
+}
+
+impl OutputChannel for VecChannel {
+    fn send(&self, data: Frame<bytes::Bytes>) {
+        self.0.borrow_mut().push_back(data)
+    }
+}
+
+/// Feeds `input` followed by End through `node` and returns the frames it sent
+fn run<I: MxlData, O: MxlData>(node: &mut dyn MxlNode, input: Vec<I>) -> Vec<Frame<O>> {
+    let input_frames: Frames = Default::default();
+    let output_frames: Frames = Default::default();
+
+    for i in input {
+        input_frames
+            .borrow_mut()
+            .push_back(i.into_buffer_frame().unwrap());
+    }
+    input_frames.borrow_mut().push_back(Frame::End);
+
+    let mut ctx = MxlNodeCtx::new();
+    ctx.inputs = HashMap::from([(
+        0,
+        Input::new(vec![Box::new(VecChannel(input_frames.clone()))]),
+    )]);
+    ctx.outputs = HashMap::from([(
+        0,
+        Output {
+            output_chs: vec![Box::new(VecChannel(output_frames.clone()))],
+        },
+    )]);
+
+    while !input_frames.borrow().is_empty() {
+        let _ = node.tick(&mut ctx);
+    }
+
+    let frames: Vec<_> = output_frames.borrow_mut().drain(..).collect();
+    frames.into_iter().map(O::from_buffer_frame).collect()
+}
+
+/// Keeps non-empty lines, uppercased
+#[node]
+fn shout(line: String) -> Option<String> {
+    if line.is_empty() {
+        None
+    } else {
+        Some(line.to_uppercase())
+    }
+}
+
+#[node(name = "ParseNode", label = "parse numbers")]
+fn parse(line: String) -> Result<u32> {
+    line.parse().map_err(|_| anyhow!("not a number: {}", line))
+}
+
+#[derive(Default)]
+struct LineCounter {
+    lines: u32,
+}
+
+#[node]
+impl LineCounter {
+    fn on_data(&mut self, line: String) -> Option<String> {
+        self.lines += 1;
+        Some(line)
+    }
+
+    fn on_end(&mut self) -> Option<String> {
+        Some(format!("{} lines", self.lines))
+    }
+}
+
+fn data<T>(frames: Vec<Frame<T>>) -> Vec<T> {
+    frames
+        .into_iter()
+        .filter_map(|f| match f {
+            Frame::Data(d) => Some(d),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn fn_node() {
+    let mut node = Shout::node();
+    assert_eq!(node.default_label(), Some("shout".to_owned()));
+
+    let out = run::<String, String>(&mut node, vec!["a".into(), "".into(), "b".into()]);
+    assert!(matches!(out.last(), Some(Frame::End)));
+    assert_eq!(data(out), vec!["A", "B"]);
+}
+
+#[test]
+fn node_fn_can_still_be_called() {
+    assert_eq!(shout("a".to_owned()), Some("A".to_owned()));
+    assert!(parse("x".to_owned()).is_err());
+}
+
+#[test]
+fn fallible_fn_node_routes_errors() {
+    let mut node = ParseNode::node();
+    assert_eq!(node.default_label(), Some("parse numbers".to_owned()));
+
+    let out = run::<String, u32>(&mut node, vec!["1".into(), "x".into(), "3".into()]);
+
+    assert!(matches!(out[1], Frame::Error));
+    assert_eq!(data(out), vec![1, 3]);
+}
+
+#[test]
+fn impl_node_runs_end_hook() {
+    let mut node = OperatorNode::new(LineCounter::default());
+    assert_eq!(node.default_label(), Some("LineCounter".to_owned()));
+
+    let out = run::<String, String>(&mut node, vec!["a".into(), "b".into()]);
+    assert_eq!(data(out), vec!["a", "b", "2 lines"]);
+}
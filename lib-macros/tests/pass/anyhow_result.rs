@@ -0,0 +1,11 @@
+mod app {
+    use mixlayer::MxlGraph;
+    use mixlayer_macros::builder;
+
+    #[builder]
+    fn main() -> anyhow::Result<MxlGraph> {
+        Ok(MxlGraph::new())
+    }
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+mod app {
+    use mixlayer as mxl;
+    use mixlayer_macros::builder;
+
+    #[builder(crate = "mxl")]
+    fn main() -> mxl::Result<mxl::MxlGraph> {
+        Ok(mxl::MxlGraph::new())
+    }
+}
+
+fn main() {}
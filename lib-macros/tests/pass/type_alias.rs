@@ -0,0 +1,13 @@
+mod app {
+    use mixlayer::MxlGraph;
+    use mixlayer_macros::builder;
+
+    type AppResult = mixlayer::Result<MxlGraph>;
+
+    #[builder]
+    fn main() -> AppResult {
+        Ok(MxlGraph::new())
+    }
+}
+
+fn main() {}
@@ -0,0 +1,153 @@
+use mixlayer::mixdb::{FieldType, MixDbSchema, Schema, SchemaField};
+use mixlayer::JsonObject;
+use serde::Serialize;
+
+#[derive(MixDbSchema)]
+#[allow(dead_code)]
+struct Author {
+    name: String,
+    #[mixdb(keyword)]
+    email: Option<String>,
+}
+
+#[derive(MixDbSchema)]
+#[allow(dead_code)]
+struct Article {
+    #[mixdb(keyword)]
+    id: String,
+    title: String,
+    #[mixdb(rename = "wordCount")]
+    word_count: u32,
+    rating: Option<f64>,
+    #[mixdb(date)]
+    published: String,
+    #[mixdb(keyword)]
+    tags: Vec<String>,
+    #[mixdb(nested)]
+    author: Author,
+    #[mixdb(skip)]
+    cached_html: Vec<u8>,
+}
+
+fn doc(value: serde_json::Value) -> JsonObject {
+    JsonObject::try_from(value).unwrap()
+}
+
+#[test]
+fn derived_schema_types_and_validates_fields() {
+    let schema = Article::schema();
+
+    let author = vec![
+        SchemaField::new("name", FieldType::Text),
+        SchemaField::new("email", FieldType::Keyword).optional(),
+    ];
+
+    assert_eq!(
+        schema,
+        Schema::new(vec![
+            SchemaField::new("id", FieldType::Keyword),
+            SchemaField::new("title", FieldType::Text),
+            SchemaField::new("wordCount", FieldType::Integer),
+            SchemaField::new("rating", FieldType::Float).optional(),
+            SchemaField::new("published", FieldType::Date),
+            SchemaField::new("tags", FieldType::Keyword).repeated(),
+            SchemaField::new("author", FieldType::Nested(author)),
+        ])
+    );
+
+    assert_eq!(
+        schema.field("author.email").map(|f| &f.field_type),
+        Some(&FieldType::Keyword)
+    );
+
+    let valid = doc(serde_json::json!({
+        "id": "a-1",
+        "title": "Hello",
+        "wordCount": 120,
+        "published": "2024-03-01T10:00:00Z",
+        "tags": ["news", "rust"],
+        "author": { "name": "Sam" },
+        "extra": "fields the schema doesn't mention are allowed",
+    }));
+    schema.validate(&valid).unwrap();
+
+    let invalid = doc(serde_json::json!({
+        "id": "a-2",
+        "title": "Hello",
+        "wordCount": 1.5,
+        "published": "2024-03-01",
+        "tags": [],
+        "author": { "name": "Sam" },
+    }));
+    let err = schema.validate(&invalid).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "field wordCount should be an integer, found 1.5"
+    );
+
+    let missing = doc(serde_json::json!({
+        "id": "a-3",
+        "title": "Hello",
+        "wordCount": 1,
+        "published": 1709287200000u64,
+        "tags": [],
+        "author": { "email": "sam@example.com" },
+    }));
+    let err = schema.validate(&missing).unwrap_err();
+    assert_eq!(err.to_string(), "missing required field author.name");
+}
+
+#[derive(MixDbSchema, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[allow(dead_code)]
+struct Listing {
+    listing_id: u64,
+    #[serde(rename = "headline")]
+    title: String,
+    #[serde(rename(serialize = "priceUsd", deserialize = "price"))]
+    price_usd: f64,
+    #[serde(rename = "ignored")]
+    #[mixdb(rename = "sellerName")]
+    seller: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    updated_at: Option<u64>,
+    #[serde(skip)]
+    cache_key: String,
+    #[serde(skip_serializing)]
+    draft_notes: String,
+}
+
+#[test]
+fn derived_schema_follows_serde_renames() {
+    let listing = Listing {
+        listing_id: 1,
+        title: "Bike".to_owned(),
+        price_usd: 120.0,
+        seller: "Sam".to_owned(),
+        updated_at: Some(1709287200),
+        cache_key: String::new(),
+        draft_notes: String::new(),
+    };
+
+    assert_eq!(
+        Listing::schema(),
+        Schema::new(vec![
+            SchemaField::new("listingId", FieldType::Integer),
+            SchemaField::new("headline", FieldType::Text),
+            SchemaField::new("priceUsd", FieldType::Float),
+            SchemaField::new("sellerName", FieldType::Text),
+            SchemaField::new("updatedAt", FieldType::Integer).optional(),
+        ])
+    );
+
+    // the names match the keys serde writes
+    let document = serde_json::to_value(&listing).unwrap();
+    for field in ["listingId", "headline", "priceUsd", "updatedAt"] {
+        assert!(document.get(field).is_some(), "{} isn't serialized", field);
+    }
+
+    // fields serde leaves out aren't in the schema either
+    for field in ["cacheKey", "draftNotes"] {
+        assert!(document.get(field).is_none());
+    }
+}
@@ -0,0 +1,11 @@
+mod app {
+    use mixlayer::{MxlGraph, Result};
+    use mixlayer_macros::builder;
+
+    #[builder(crate = "not a path")]
+    fn main() -> Result<MxlGraph> {
+        Ok(MxlGraph::new())
+    }
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+mod app {
+    use mixlayer_macros::builder;
+
+    #[builder]
+    fn main() {}
+}
+
+fn main() {}
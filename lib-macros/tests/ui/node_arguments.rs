@@ -0,0 +1,8 @@
+use mixlayer_macros::node;
+
+#[node]
+fn join_lines(a: String, b: String) -> String {
+    a + &b
+}
+
+fn main() {}
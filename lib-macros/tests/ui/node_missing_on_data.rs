@@ -0,0 +1,14 @@
+use mixlayer_macros::node;
+
+struct Counter {
+    count: u32,
+}
+
+#[node]
+impl Counter {
+    fn on_end(&mut self) -> Option<u32> {
+        Some(self.count)
+    }
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+mod app {
+    use mixlayer::MxlGraph;
+    use mixlayer_macros::builder;
+
+    #[builder]
+    fn main() -> &'static MxlGraph {
+        unimplemented!()
+    }
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+mod app {
+    use mixlayer::{MxlGraph, Result};
+    use mixlayer_macros::builder;
+
+    #[builder]
+    fn build() -> Result<MxlGraph> {
+        Ok(MxlGraph::new())
+    }
+}
+
+fn main() {}
@@ -0,0 +1,15 @@
+use mixlayer::mixdb::MixDbSchema;
+
+#[derive(serde::Serialize)]
+struct Address {
+    city: String,
+}
+
+#[derive(MixDbSchema, serde::Serialize)]
+struct Customer {
+    name: String,
+    #[serde(flatten)]
+    address: Address,
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+mod app {
+    use mixlayer::Result;
+    use mixlayer_macros::builder;
+
+    type AppResult = Result<String>;
+
+    #[builder]
+    fn main() -> AppResult {
+        Ok(String::new())
+    }
+}
+
+fn main() {}
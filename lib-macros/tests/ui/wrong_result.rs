@@ -0,0 +1,11 @@
+mod app {
+    use mixlayer::Result;
+    use mixlayer_macros::builder;
+
+    #[builder]
+    fn main() -> Result<String> {
+        Ok(String::new())
+    }
+}
+
+fn main() {}
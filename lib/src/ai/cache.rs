@@ -0,0 +1,387 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use anyhow::{anyhow, Context};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::EmbeddingModel;
+use crate::io::{MxlFile, MxlFileMode};
+use crate::Result;
+
+/// An embedding saved by `CachedEmbeddingModel`, keyed by model id, dimensions and the
+/// sha256 of the embedded text
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedEmbedding {
+    pub key: String,
+    pub embedding: Vec<f32>,
+}
+
+/// Where `CachedEmbeddingModel` keeps embeddings between runs
+pub trait EmbeddingCacheStore {
+    /// Reads every saved embedding, called once when the cache is created
+    fn load(&mut self) -> Result<Vec<CachedEmbedding>>;
+
+    /// Saves an embedding computed in this run
+    fn store(&mut self, entry: &CachedEmbedding) -> Result<()>;
+}
+
+/// Keeps embeddings in a file of JSON lines through the host.
+///
+/// The host can't append to files, so loading rewrites the entries it read before new ones are
+/// added. Several models can share a file, their keys never collide.
+pub struct EmbeddingCacheFile {
+    path: PathBuf,
+    writer: Option<MxlFile>,
+}
+
+impl EmbeddingCacheFile {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_owned(),
+            writer: None,
+        }
+    }
+
+    fn write_entry(&mut self, entry: &CachedEmbedding) -> Result<()> {
+        let writer = match &mut self.writer {
+            Some(writer) => writer,
+            None => return Err(anyhow!("embedding cache file wasn't loaded")),
+        };
+
+        let line = serde_json::to_string(entry).context("error serializing embedding")?;
+        writeln!(writer, "{}", line)?;
+
+        Ok(())
+    }
+}
+
+impl EmbeddingCacheStore for EmbeddingCacheFile {
+    fn load(&mut self) -> Result<Vec<CachedEmbedding>> {
+        let mut entries = Vec::new();
+
+        match MxlFile::open(&self.path, MxlFileMode::Read) {
+            Ok(file) => {
+                for line in BufReader::new(file).lines() {
+                    let line = line?;
+
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+
+                    let entry: CachedEmbedding =
+                        serde_json::from_str(&line).with_context(|| {
+                            format!("error parsing embedding cache {}", self.path.display())
+                        })?;
+                    entries.push(entry);
+                }
+            }
+            Err(err) => debug!(
+                "starting empty embedding cache {}: {}",
+                self.path.display(),
+                err
+            ),
+        }
+
+        self.writer = Some(MxlFile::open(&self.path, MxlFileMode::Write)?);
+
+        for entry in entries.iter() {
+            self.write_entry(entry)?;
+        }
+
+        Ok(entries)
+    }
+
+    fn store(&mut self, entry: &CachedEmbedding) -> Result<()> {
+        self.write_entry(entry)
+    }
+}
+
+/// Hit and miss counts of a `CachedEmbeddingModel`. Clones share the counts, so they can be
+/// read after the model is moved into a sink.
+#[derive(Debug, Clone, Default)]
+pub struct EmbeddingCacheStats {
+    hits: Arc<AtomicU64>,
+    misses: Arc<AtomicU64>,
+}
+
+impl EmbeddingCacheStats {
+    /// Texts whose embedding came from the cache
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Texts the wrapped model had to embed
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Share of texts served from the cache, 0 before any text is embedded
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits() + self.misses();
+
+        if total == 0 {
+            0.0
+        } else {
+            self.hits() as f64 / total as f64
+        }
+    }
+
+    fn record(&self, hits: usize, misses: usize) {
+        self.hits.fetch_add(hits as u64, Ordering::Relaxed);
+        self.misses.fetch_add(misses as u64, Ordering::Relaxed);
+    }
+}
+
+struct CacheState {
+    entries: HashMap<String, Vec<f32>>,
+    store: Option<Box<dyn EmbeddingCacheStore + Send>>,
+}
+
+impl CacheState {
+    fn insert(&mut self, key: String, embedding: Vec<f32>) -> Result<()> {
+        if let Some(store) = &mut self.store {
+            store
+                .store(&CachedEmbedding {
+                    key: key.clone(),
+                    embedding: embedding.clone(),
+                })
+                .context("error saving embedding to cache")?;
+        }
+
+        self.entries.insert(key, embedding);
+
+        Ok(())
+    }
+}
+
+/// Wraps an embedding model so texts it has embedded before aren't embedded, and billed,
+/// again. Works anywhere an `EmbeddingModel` is taken, e.g. `MxlCollectionSink::vector_index`.
+pub struct CachedEmbeddingModel<E> {
+    model: E,
+    state: Mutex<CacheState>,
+    stats: EmbeddingCacheStats,
+}
+
+impl<E: EmbeddingModel> CachedEmbeddingModel<E> {
+    /// Caches embeddings in memory for as long as the model lives
+    pub fn new(model: E) -> Self {
+        Self {
+            model,
+            state: Mutex::new(CacheState {
+                entries: HashMap::new(),
+                store: None,
+            }),
+            stats: EmbeddingCacheStats::default(),
+        }
+    }
+
+    /// Caches embeddings in `store`, starting with the ones earlier runs saved there
+    pub fn with_store<S>(model: E, mut store: S) -> Result<Self>
+    where
+        S: EmbeddingCacheStore + Send + 'static,
+    {
+        let entries = store.load().context("error loading embedding cache")?;
+
+        debug!("loaded {} cached embeddings", entries.len());
+
+        Ok(Self {
+            model,
+            state: Mutex::new(CacheState {
+                entries: entries.into_iter().map(|e| (e.key, e.embedding)).collect(),
+                store: Some(Box::new(store)),
+            }),
+            stats: EmbeddingCacheStats::default(),
+        })
+    }
+
+    /// Caches embeddings in a file relative to the app's working directory
+    pub fn with_file<P: AsRef<Path>>(model: E, path: P) -> Result<Self> {
+        Self::with_store(model, EmbeddingCacheFile::new(path))
+    }
+
+    pub fn stats(&self) -> EmbeddingCacheStats {
+        self.stats.clone()
+    }
+
+    pub fn model(&self) -> &E {
+        &self.model
+    }
+
+    fn key(&self, text: &str) -> String {
+        format!(
+            "{}:{}:{:x}",
+            self.model.model_id(),
+            self.model.num_dims(),
+            Sha256::digest(text.as_bytes())
+        )
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, CacheState>> {
+        self.state
+            .lock()
+            .map_err(|_| anyhow!("embedding cache lock poisoned"))
+    }
+}
+
+impl<E: EmbeddingModel> EmbeddingModel for CachedEmbeddingModel<E> {
+    fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let key = self.key(text);
+
+        if let Some(embedding) = self.lock()?.entries.get(&key) {
+            self.stats.record(1, 0);
+            return Ok(embedding.clone());
+        }
+
+        let embedding = self.model.embed(text)?;
+        self.stats.record(0, 1);
+
+        self.lock()?.insert(key, embedding.clone())?;
+
+        Ok(embedding)
+    }
+
+    fn num_dims(&self) -> u32 {
+        self.model.num_dims()
+    }
+
+    fn token_limit(&self) -> u32 {
+        self.model.token_limit()
+    }
+
+    fn model_id(&self) -> &str {
+        self.model.model_id()
+    }
+
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        let keys: Vec<String> = texts.iter().map(|text| self.key(text)).collect();
+
+        // texts to embed, each once even if the batch repeats it
+        let mut missing: Vec<(&str, &String)> = Vec::new();
+
+        {
+            let state = self.lock()?;
+
+            for (text, key) in texts.iter().zip(keys.iter()) {
+                if !state.entries.contains_key(key) && !missing.iter().any(|(_, k)| *k == key) {
+                    missing.push((text, key));
+                }
+            }
+        }
+
+        if !missing.is_empty() {
+            let missing_texts: Vec<&str> = missing.iter().map(|(text, _)| *text).collect();
+            let embeddings = self.model.embed_batch(&missing_texts)?;
+
+            if embeddings.len() != missing.len() {
+                return Err(anyhow!(
+                    "embedding model returned {} vectors for {} texts",
+                    embeddings.len(),
+                    missing.len()
+                ));
+            }
+
+            let mut state = self.lock()?;
+
+            for ((_, key), embedding) in missing.iter().zip(embeddings) {
+                state.insert((*key).clone(), embedding)?;
+            }
+        }
+
+        self.stats
+            .record(texts.len() - missing.len(), missing.len());
+
+        let state = self.lock()?;
+
+        keys.iter()
+            .map(|key| {
+                state
+                    .entries
+                    .get(key)
+                    .cloned()
+                    .ok_or_else(|| anyhow!("embedding missing from cache"))
+            })
+            .collect()
+    }
+
+    fn batch_size_limit(&self) -> usize {
+        self.model.batch_size_limit()
+    }
+
+    fn batch_token_limit(&self) -> u32 {
+        self.model.batch_token_limit()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Embeds a text as its length and counts the texts it was asked to embed
+    struct CountingModel(Arc<AtomicU64>);
+
+    impl EmbeddingModel for CountingModel {
+        fn embed(&self, text: &str) -> Result<Vec<f32>> {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            Ok(vec![text.len() as f32, 1.0])
+        }
+
+        fn num_dims(&self) -> u32 {
+            2
+        }
+
+        fn token_limit(&self) -> u32 {
+            100
+        }
+
+        fn model_id(&self) -> &str {
+            "counting"
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct MemoryStore(Arc<Mutex<Vec<CachedEmbedding>>>);
+
+    impl EmbeddingCacheStore for MemoryStore {
+        fn load(&mut self) -> Result<Vec<CachedEmbedding>> {
+            Ok(self.0.lock().unwrap().clone())
+        }
+
+        fn store(&mut self, entry: &CachedEmbedding) -> Result<()> {
+            self.0.lock().unwrap().push(entry.clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn cached_model_embeds_each_text_once_across_runs() {
+        let embedded = Arc::new(AtomicU64::new(0));
+        let store = MemoryStore::default();
+
+        let cached =
+            CachedEmbeddingModel::with_store(CountingModel(embedded.clone()), store.clone())
+                .unwrap();
+        let stats = cached.stats();
+
+        cached.embed("hello").unwrap();
+        let batch = cached.embed_batch(&["hello", "world!", "world!"]).unwrap();
+
+        assert_eq!(batch, vec![vec![5.0, 1.0], vec![6.0, 1.0], vec![6.0, 1.0]]);
+        assert_eq!(embedded.load(Ordering::Relaxed), 2);
+        assert_eq!((stats.hits(), stats.misses()), (2, 2));
+
+        // a later run loads the saved embeddings instead of embedding again
+        let rerun =
+            CachedEmbeddingModel::with_store(CountingModel(embedded.clone()), store).unwrap();
+
+        rerun.embed_batch(&["world!", "hello"]).unwrap();
+
+        assert_eq!(embedded.load(Ordering::Relaxed), 2);
+        assert_eq!((rerun.stats().hits(), rerun.stats().misses()), (2, 0));
+        assert_eq!(rerun.stats().hit_rate(), 1.0);
+    }
+}
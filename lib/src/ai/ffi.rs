@@ -1,6 +1,8 @@
 use mixlayer_runtime_ffi::prost::Message;
 use mixlayer_runtime_ffi::protos::BatchChatCompletionResponse;
 use mixlayer_runtime_ffi::protos::ChatCompletionResponse;
+use mixlayer_runtime_ffi::protos::CreateEmbeddingBatchRequest;
+use mixlayer_runtime_ffi::protos::CreateEmbeddingBatchResponse;
 use mixlayer_runtime_ffi::protos::CreateEmbeddingRequest;
 use mixlayer_runtime_ffi::protos::CreateEmbeddingResponse;
 
@@ -12,6 +14,9 @@ extern "C" {
     // proto: CreateEmbeddingRequest -> CreateEmbeddingRespones
     fn _embedding_request(request: *const ByteBuffer) -> *mut ByteBuffer;
 
+    // proto: CreateEmbeddingBatchRequest -> CreateEmbeddingBatchResponse
+    fn _embedding_batch_request(request: *const ByteBuffer) -> *mut ByteBuffer;
+
     // proto: ChatCompletionRequest -> ChatCompletionResponse
     fn _chat_completion_request(request: *const ByteBuffer) -> *mut ByteBuffer;
 
@@ -29,6 +34,18 @@ pub fn embedding_request(request: CreateEmbeddingRequest) -> Result<CreateEmbedd
     Ok(CreateEmbeddingResponse::decode(response_bytes)?)
 }
 
+pub fn embedding_batch_request(
+    request: CreateEmbeddingBatchRequest,
+) -> Result<CreateEmbeddingBatchResponse> {
+    let request_bytes: ByteBuffer = request.encode_to_vec().into();
+    let response_bytes: Box<ByteBuffer> =
+        unsafe { Box::from_raw(_embedding_batch_request(&request_bytes)) };
+
+    let response_bytes = response_bytes.into_bytes();
+
+    Ok(CreateEmbeddingBatchResponse::decode(response_bytes)?)
+}
+
 pub fn chat_completion_request(
     req: mixlayer_runtime_ffi::protos::ChatCompletionRequest,
 ) -> Result<String> {
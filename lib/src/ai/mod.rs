@@ -1,5 +1,6 @@
 use crate::Result;
 
+mod cache;
 mod ffi;
 mod openai;
 
@@ -12,6 +13,30 @@ pub trait EmbeddingModel {
 
     // maximum number of input tokens this model can accept
     fn token_limit(&self) -> u32;
+
+    // identifies the model in embedding caches, keep it stable across releases and give models
+    // producing different vectors for the same text different ids
+    fn model_id(&self) -> &str;
+
+    // embed several texts at once, returns one vector per text in the same order
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        texts.iter().map(|text| self.embed(text)).collect()
+    }
+
+    // maximum number of texts embed_batch should be called with
+    fn batch_size_limit(&self) -> usize {
+        1
+    }
+
+    // maximum number of input tokens across all texts of a batch
+    fn batch_token_limit(&self) -> u32 {
+        self.token_limit()
+    }
+}
+
+/// Rough number of tokens in `text` for batching, about 4 bytes per token for English text
+pub(crate) fn estimate_tokens(text: &str) -> u32 {
+    text.len().div_ceil(4) as u32
 }
 
 pub trait ChatCompletionModel {
@@ -19,6 +44,10 @@ pub trait ChatCompletionModel {
     fn token_limit(&self) -> u32;
 }
 
+pub use cache::{
+    CachedEmbedding, CachedEmbeddingModel, EmbeddingCacheFile, EmbeddingCacheStats,
+    EmbeddingCacheStore,
+};
 pub use openai::FFIChatCompletionModel;
 pub use openai::Gpt4;
 pub use openai::Gpt4Turbo;
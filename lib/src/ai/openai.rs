@@ -1,5 +1,7 @@
+use anyhow::anyhow;
 use mixlayer_runtime_ffi::protos::{
-    ChatCompletionModelProto, ChatCompletionRequest, CreateEmbeddingRequest, EmbeddingModelProto,
+    ChatCompletionModelProto, ChatCompletionRequest, CreateEmbeddingBatchRequest,
+    CreateEmbeddingRequest, EmbeddingModelProto,
 };
 
 use super::{ChatCompletionModel, EmbeddingModel};
@@ -27,6 +29,36 @@ impl EmbeddingModel for OpenAIAda002 {
     fn token_limit(&self) -> u32 {
         8191
     }
+
+    fn model_id(&self) -> &str {
+        "openai/text-embedding-ada-002"
+    }
+
+    fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        let resp = super::ffi::embedding_batch_request(CreateEmbeddingBatchRequest {
+            inputs: texts.iter().map(|text| text.to_string()).collect(),
+            model: EmbeddingModelProto::OpenAiAda002 as i32,
+        })?;
+
+        if resp.embeddings.len() != texts.len() {
+            return Err(anyhow!(
+                "expected {} embeddings, got {}",
+                texts.len(),
+                resp.embeddings.len()
+            ));
+        }
+
+        Ok(resp.embeddings.into_iter().map(|e| e.embedding).collect())
+    }
+
+    // limits of the OpenAI embeddings endpoint
+    fn batch_size_limit(&self) -> usize {
+        2048
+    }
+
+    fn batch_token_limit(&self) -> u32 {
+        300_000
+    }
 }
 
 pub trait FFIChatCompletionModel {
@@ -32,10 +32,14 @@ impl MxlFile {
 
         //TODO may not handle non-unicode characters how we want
         let path = format!("{}", path.display());
-        let path_buf: ByteBuffer = path.into();
+        let path_buf: ByteBuffer = path.clone().into();
 
         let handle = unsafe { _valence_file_open(&path_buf, mode as i32) };
 
+        if handle < 0 {
+            return Err(anyhow::anyhow!("error opening file {}", path));
+        }
+
         Ok(Self { handle })
     }
 
@@ -1,5 +1,7 @@
 mod file;
 mod http;
+mod spill;
 
 pub use self::http::VHttpClient;
 pub use file::{MxlFile, MxlFileMode};
+pub use spill::MxlFileSpill;
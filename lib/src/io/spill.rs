@@ -0,0 +1,38 @@
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use mixlayer_graph::SpillStore;
+
+use super::{MxlFile, MxlFileMode};
+use crate::Result;
+
+/// Spills operator state to files through the host, under a directory relative to the app's
+/// working directory
+pub struct MxlFileSpill {
+    dir: PathBuf,
+}
+
+impl MxlFileSpill {
+    pub fn new<P: AsRef<Path>>(dir: P) -> Self {
+        Self {
+            dir: dir.as_ref().to_owned(),
+        }
+    }
+}
+
+impl SpillStore for MxlFileSpill {
+    fn create(&mut self, name: &str) -> Result<Box<dyn Write + Send>> {
+        let file = MxlFile::open(self.dir.join(name), MxlFileMode::Write)?;
+        Ok(Box::new(file))
+    }
+
+    fn open(&mut self, name: &str) -> Result<Box<dyn Read + Send>> {
+        let file = MxlFile::open(self.dir.join(name), MxlFileMode::Read)?;
+        Ok(Box::new(file))
+    }
+
+    fn remove(&mut self, name: &str) -> Result<()> {
+        // the host can't delete files yet, truncate the run instead so it doesn't take up space
+        MxlFile::open(self.dir.join(name), MxlFileMode::Write)?.close()
+    }
+}
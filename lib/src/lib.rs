@@ -12,15 +12,20 @@ pub use mixlayer_runtime_ffi::{ByteBuffer, FFIMessage};
 
 pub use graph::{
     Frame, Input, InputChannel, Output, OutputChannel, MxlEdge, MxlGraph, MxlNodeId, MxlNodeRef, MxlNodeType,
+    Emitter, JoinKind, OperatorNode, StreamOperator,
 };
 
 pub use mixlayer_data::{JsonObject, JsonMxlData, JsonValue};
-pub use mixlayer_macros::builder;
+pub use mixlayer_macros::{builder, node};
 
 pub use anyhow::Result;
 
 use log::error;
-use mixlayer_runtime_ffi::protos::{self, VEdgeProto, VGraphProto, VNodeTypeProto};
+use mixlayer_runtime_ffi::prost::Message;
+use mixlayer_runtime_ffi::protos::{
+    self, init_result, InitError, InitResult, JoinKindProto, VEdgeProto, VGraphProto,
+    VNodeTypeProto,
+};
 
 extern "C" {
     /// Logs a message on the WebAssembly host.
@@ -40,6 +45,14 @@ extern "C" {
     pub fn _valence_edge_channel_recv(id: *const ByteBuffer) -> *mut ByteBuffer;
     pub fn _valence_edge_is_finished(id: *const ByteBuffer) -> i32;
 
+    /// Marks an edge as closed by its reader, the runtime drops anything sent on it afterwards
+    ///
+    /// # Arguments
+    ///
+    /// * `id` a `ByteBuffer` containing a protobuf-encoded VEdge
+    pub fn _valence_edge_channel_close(id: *const ByteBuffer) -> ();
+    pub fn _valence_edge_is_closed(id: *const ByteBuffer) -> i32;
+
     pub fn _valence_unixtime() -> i32;
 
     pub fn _valence_uuid_v4() -> *mut ByteBuffer;
@@ -49,6 +62,16 @@ pub fn valence_unixtime() -> i32 {
     unsafe { _valence_unixtime() }
 }
 
+/// Reads the time from the host, used by nodes that expire state such as symmetric joins. The
+/// host reports whole seconds, so durations nodes measure with it have one-second resolution.
+pub struct ValenceClock;
+
+impl graph::Clock for ValenceClock {
+    fn now(&self) -> u64 {
+        valence_unixtime().max(0) as u64
+    }
+}
+
 pub fn valence_uuid_v4() -> String {
     unsafe {
         let buf = Box::from_raw(_valence_uuid_v4());
@@ -74,27 +97,40 @@ macro_rules! vlog {
 }
 
 pub struct FFIEdgeChannel {
-    edge: VEdgeProto,
+    // the edge is encoded once, every call into the host passes it
+    edge_buf: Vec<u8>,
 }
 
 impl FFIEdgeChannel {
     pub fn for_edge(edge: VEdgeProto) -> Self {
-        Self { edge }
+        Self {
+            edge_buf: edge.encode_to_vec(),
+        }
+    }
+
+    fn edge_buf(&self) -> ByteBuffer {
+        ByteBuffer::from_slice(&self.edge_buf)
     }
 }
 
 impl OutputChannel for FFIEdgeChannel {
     fn send(&self, data: graph::Frame<mixlayer_runtime_ffi::prost::bytes::Bytes>) -> () {
-        let edge_buf: ByteBuffer = FFIMessage(&self.edge).try_into().unwrap();
+        let edge_buf = self.edge_buf();
         let frame_buf: ByteBuffer = data.into_bytes().into();
 
         unsafe { _valence_edge_channel_send(&edge_buf, &frame_buf) }
     }
+
+    fn is_closed(&self) -> bool {
+        let edge_buf = self.edge_buf();
+        let is_closed = unsafe { _valence_edge_is_closed(&edge_buf) };
+        is_closed > 0
+    }
 }
 
 impl InputChannel for FFIEdgeChannel {
     fn finished(&self) -> bool {
-        let edge_buf: ByteBuffer = FFIMessage(&self.edge).try_into().unwrap();
+        let edge_buf = self.edge_buf();
         let is_finished = unsafe { _valence_edge_is_finished(&edge_buf) };
         is_finished > 0
     }
@@ -104,7 +140,7 @@ impl InputChannel for FFIEdgeChannel {
     }
 
     fn recv(&self) -> Option<graph::Frame<mixlayer_runtime_ffi::prost::bytes::Bytes>> {
-        let edge_buf: ByteBuffer = FFIMessage(&self.edge).try_into().unwrap();
+        let edge_buf = self.edge_buf();
         let frame_buf = unsafe {
             let buf = _valence_edge_channel_recv(&edge_buf);
 
@@ -120,31 +156,41 @@ impl InputChannel for FFIEdgeChannel {
 
         Some(frame)
     }
+
+    fn close(&self) {
+        let edge_buf = self.edge_buf();
+        unsafe { _valence_edge_channel_close(&edge_buf) }
+    }
 }
 
 #[no_mangle]
 extern "C" fn _valence_tick_node(graph: *mut MxlGraph, node_id: u32) -> () {
     let graph = unsafe { Box::leak(Box::from_raw(graph)) };
 
-    let inputs = inputs_for_node(&graph, &node_id);
-    let outputs = outputs_for_node(&graph, &node_id);
+    if graph.is_finished(&node_id) {
+        return;
+    }
 
-    if let Some(node) = graph.node_mut(&node_id) {
-        let mut ctx = graph::MxlNodeCtx::new();
+    let mut ctx = graph::MxlNodeCtx::new();
 
-        ctx.inputs = inputs;
-        ctx.outputs = outputs;
+    ctx.inputs = inputs_for_node(&graph, &node_id);
+    ctx.outputs = outputs_for_node(&graph, &node_id);
 
-        //TODO error recovery, classification, retries, etc
-        match node.tick(&mut ctx) {
-            Ok(_) => (),
-            Err(err) => error!("node error: {}", err),
-        }
-    } else {
-        error!("node {} not found", node_id);
+    //TODO error recovery, classification, retries, etc
+    match graph.tick_node(&node_id, ctx) {
+        Ok(_) => (),
+        Err(err) => error!("node error: {}", err),
     }
 }
 
+/// Lets the runtime know it can stop scheduling a node, returns 1 once the node has finished
+#[no_mangle]
+extern "C" fn _valence_node_finished(graph: *mut MxlGraph, node_id: u32) -> i32 {
+    let graph = unsafe { graph.as_ref().unwrap() };
+
+    graph.is_finished(&node_id) as i32
+}
+
 fn to_edge_proto(ed: &MxlEdge) -> VEdgeProto {
     protos::VEdgeProto {
         source_node_id: ed.source_node_id,
@@ -154,6 +200,62 @@ fn to_edge_proto(ed: &MxlEdge) -> VEdgeProto {
     }
 }
 
+/// Encodes the result of an app's `main` function into the `InitResult` buffer returned to
+/// the host from `_valence_app_init`. Called from the code generated by `#[builder]`.
+#[doc(hidden)]
+pub fn app_init_result<E>(result: std::result::Result<MxlGraph, E>) -> *mut ByteBuffer
+where
+    E: Into<anyhow::Error>,
+{
+    let result = match result.map_err(Into::into) {
+        #[allow(unused_mut)]
+        Ok(mut graph) => {
+            // native runs have no host clock, so keep the system clock there
+            #[cfg(target_arch = "wasm32")]
+            graph.set_clock(ValenceClock);
+
+            let graph_ptr = Box::into_raw(Box::new(graph));
+            init_result::Result::GraphPtr(graph_ptr as usize as u64)
+        }
+        Err(err) => {
+            let init_error = InitError {
+                message: err.to_string(),
+                chain: err.chain().map(|e| e.to_string()).collect(),
+            };
+
+            // native runs have no host to report to, so print the chain the host would see
+            #[cfg(not(target_arch = "wasm32"))]
+            eprintln!("{}", format_init_error(&init_error));
+
+            init_result::Result::Error(init_error)
+        }
+    };
+
+    let init_result = InitResult {
+        result: Some(result),
+    };
+
+    let buf: ByteBuffer = FFIMessage(&init_result).try_into().unwrap();
+    Box::into_raw(Box::new(buf))
+}
+
+/// Formats an `InitError` as its message followed by a numbered list of its causes
+pub fn format_init_error(err: &InitError) -> String {
+    let mut out = format!("error building graph: {}", err.message);
+
+    let causes: Vec<&String> = err.chain.iter().skip(1).collect();
+
+    if !causes.is_empty() {
+        out.push_str("\n\nCaused by:");
+
+        for (idx, cause) in causes.iter().enumerate() {
+            out.push_str(&format!("\n    {}: {}", idx, cause));
+        }
+    }
+
+    out
+}
+
 /// allows the runtime to free a graph so the Drop traits run on all of the nodes
 #[no_mangle]
 extern "C" fn _valence_free_graph(graph: *mut MxlGraph) -> () {
@@ -185,9 +287,18 @@ extern "C" fn _valence_export_graph(graph: *mut MxlGraph) -> *const ByteBuffer {
                 MxlNodeType::Source => VNodeTypeProto::NodeTypeSource,
                 MxlNodeType::Transform => VNodeTypeProto::NodeTypeTransform,
                 MxlNodeType::Sink => VNodeTypeProto::NodeTypeSink,
-                _ => VNodeTypeProto::NodeTypeUnknown,
+                MxlNodeType::Join => VNodeTypeProto::NodeTypeJoin,
             };
 
+            let join_kind = metadata.join_kind.map(|kind| match kind {
+                JoinKind::Inner => JoinKindProto::JoinKindInner,
+                JoinKind::Left => JoinKindProto::JoinKindLeft,
+                JoinKind::Right => JoinKindProto::JoinKindRight,
+                JoinKind::FullOuter => JoinKindProto::JoinKindFullOuter,
+                JoinKind::Semi => JoinKindProto::JoinKindSemi,
+                JoinKind::Anti => JoinKindProto::JoinKindAnti,
+            });
+
             let info = protos::VNodeInfo {
                 node_id,
                 node_type: node_type as i32,
@@ -195,6 +306,7 @@ extern "C" fn _valence_export_graph(graph: *mut MxlGraph) -> *const ByteBuffer {
                 node_label: metadata.label.clone(),
                 input_type: metadata.input_type.clone(),
                 output_type: metadata.output_type.clone(),
+                join_kind: join_kind.map(|kind| kind as i32),
             };
             (node_id, info)
         })
@@ -211,8 +323,7 @@ extern "C" fn _valence_export_graph(graph: *mut MxlGraph) -> *const ByteBuffer {
 }
 
 pub fn edge_channel(edge: &MxlEdge) -> FFIEdgeChannel {
-    let edge = to_edge_proto(edge);
-    FFIEdgeChannel { edge }
+    FFIEdgeChannel::for_edge(to_edge_proto(edge))
 }
 
 fn inputs_for_node(graph: &MxlGraph, node_id: &MxlNodeId) -> HashMap<u32, Input> {
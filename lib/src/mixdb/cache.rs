@@ -0,0 +1,67 @@
+use anyhow::{anyhow, Context, Result};
+use mixlayer_runtime_ffi::{
+    prost::Message,
+    protos::{MixDbCreateCollectionProto, MixDbWriteMode, MixDbWriteProto, MixDbWriteResult},
+    ByteBuffer,
+};
+
+use super::{_mixdb_create_coll, _mixdb_write, host_request, MixDb, MxlCollectionSource};
+use crate::ai::{CachedEmbedding, EmbeddingCacheStore};
+
+/// Keeps `CachedEmbeddingModel` embeddings in a mixdb collection, one document per embedding
+/// with the cache key as its id
+pub struct MixDbEmbeddingCache {
+    db: MixDb,
+    coll_name: String,
+}
+
+impl MixDbEmbeddingCache {
+    pub fn new(db: &MixDb, collection: &str) -> Self {
+        Self {
+            db: db.clone(),
+            coll_name: collection.to_owned(),
+        }
+    }
+}
+
+impl EmbeddingCacheStore for MixDbEmbeddingCache {
+    fn load(&mut self) -> Result<Vec<CachedEmbedding>> {
+        let create_proto = MixDbCreateCollectionProto {
+            db_name: self.db.name().to_owned(),
+            collection: self.coll_name.clone(),
+            element_type: std::any::type_name::<CachedEmbedding>().to_owned(),
+            id_field: "key".to_owned(),
+            schema: None,
+        };
+
+        let create_buf: ByteBuffer = create_proto.encode_to_vec().into();
+        unsafe { _mixdb_create_coll(&create_buf) };
+
+        MxlCollectionSource::new(&self.db, &self.coll_name)
+            .batch_size(1000)
+            .read_all()?
+            .into_iter()
+            .map(|document| {
+                serde_json::from_value(document.into_value())
+                    .context("error parsing cached embedding")
+            })
+            .collect()
+    }
+
+    fn store(&mut self, entry: &CachedEmbedding) -> Result<()> {
+        let write_proto = MixDbWriteProto {
+            db_name: self.db.name().to_owned(),
+            collection: self.coll_name.clone(),
+            json: serde_json::to_string(entry).context("error serializing embedding")?,
+            mode: MixDbWriteMode::WriteModeUpsert as i32,
+        };
+
+        let result: MixDbWriteResult = host_request(write_proto, _mixdb_write)?;
+
+        if !result.error.is_empty() {
+            return Err(anyhow!("error caching embedding: {}", result.error));
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,253 @@
+use std::ops::{Bound, Not, RangeBounds};
+
+use anyhow::Result;
+use mixlayer_runtime_ffi::protos::{
+    mix_db_filter, MixDbFieldValue, MixDbFieldValues, MixDbFilter, MixDbFilterList,
+    MixDbRangeFilter,
+};
+use serde_json::Value;
+
+/// A condition on the fields of a document, accepted by vector search, full text search and
+/// `MxlCollectionSource`. Fields of nested objects are separated by dots, e.g. `author.name`.
+///
+/// ```ignore
+/// let filter = Filter::eq("tenant", "acme")
+///     .and(Filter::is_in("type", ["pdf", "html"]))
+///     .and(Filter::range("published", "2024-01-01".."2025-01-01"))
+///     .and(!Filter::exists("deleted_at"));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    Eq(String, Value),
+    In(String, Vec<Value>),
+    Range(FieldRange),
+    And(Vec<Filter>),
+    Or(Vec<Filter>),
+    Not(Box<Filter>),
+    Exists(String),
+}
+
+impl Filter {
+    /// The field equals `value`
+    pub fn eq(field: &str, value: impl Into<Value>) -> Self {
+        Filter::Eq(field.to_owned(), value.into())
+    }
+
+    /// The field equals one of `values`
+    pub fn is_in<V: Into<Value>>(field: &str, values: impl IntoIterator<Item = V>) -> Self {
+        Filter::In(
+            field.to_owned(),
+            values.into_iter().map(Into::into).collect(),
+        )
+    }
+
+    /// The integer, float or date field is in `range`, e.g. `Filter::range("year", 2000..=2010)`
+    pub fn range<T, R>(field: &str, range: R) -> Self
+    where
+        T: Into<Value> + Clone,
+        R: RangeBounds<T>,
+    {
+        Filter::Range(FieldRange::new(field, range))
+    }
+
+    /// The document has the field and it isn't null
+    pub fn exists(field: &str) -> Self {
+        Filter::Exists(field.to_owned())
+    }
+
+    /// Both filters match
+    pub fn and(self, other: Filter) -> Self {
+        match self {
+            Filter::And(mut filters) => {
+                filters.push(other);
+                Filter::And(filters)
+            }
+            filter => Filter::And(vec![filter, other]),
+        }
+    }
+
+    /// Either filter matches
+    pub fn or(self, other: Filter) -> Self {
+        match self {
+            Filter::Or(mut filters) => {
+                filters.push(other);
+                Filter::Or(filters)
+            }
+            filter => Filter::Or(vec![filter, other]),
+        }
+    }
+
+    pub(crate) fn proto(&self) -> Result<MixDbFilter> {
+        let list = |filters: &[Filter]| -> Result<MixDbFilterList> {
+            Ok(MixDbFilterList {
+                filters: filters.iter().map(Filter::proto).collect::<Result<_>>()?,
+            })
+        };
+
+        let filter = match self {
+            Filter::Eq(field, value) => mix_db_filter::Filter::Eq(MixDbFieldValue {
+                field_name: field.clone(),
+                value_json: serde_json::to_string(value)?,
+            }),
+            Filter::In(field, values) => mix_db_filter::Filter::AnyOf(MixDbFieldValues {
+                field_name: field.clone(),
+                values_json: values
+                    .iter()
+                    .map(serde_json::to_string)
+                    .collect::<serde_json::Result<_>>()?,
+            }),
+            Filter::Range(range) => mix_db_filter::Filter::Range(range.proto()?),
+            Filter::And(filters) => mix_db_filter::Filter::And(list(filters)?),
+            Filter::Or(filters) => mix_db_filter::Filter::Or(list(filters)?),
+            Filter::Not(filter) => mix_db_filter::Filter::Not(Box::new(filter.proto()?)),
+            Filter::Exists(field) => mix_db_filter::Filter::Exists(field.clone()),
+        };
+
+        Ok(MixDbFilter {
+            filter: Some(filter),
+        })
+    }
+}
+
+impl Not for Filter {
+    type Output = Filter;
+
+    /// The filter doesn't match
+    fn not(self) -> Filter {
+        match self {
+            Filter::Not(filter) => *filter,
+            filter => Filter::Not(Box::new(filter)),
+        }
+    }
+}
+
+/// Encodes an optional filter for a request proto
+pub(crate) fn filter_proto(filter: Option<&Filter>) -> Result<Option<MixDbFilter>> {
+    filter.map(Filter::proto).transpose()
+}
+
+/// Bounds on a typed field, any of them may be unset
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FieldRange {
+    pub field: String,
+    pub gt: Option<Value>,
+    pub gte: Option<Value>,
+    pub lt: Option<Value>,
+    pub lte: Option<Value>,
+}
+
+impl FieldRange {
+    /// Bounds `field` by a Rust range, e.g. `FieldRange::new("year", 2000..2010)`
+    pub fn new<T, R>(field: &str, range: R) -> Self
+    where
+        T: Into<Value> + Clone,
+        R: RangeBounds<T>,
+    {
+        let mut field_range = FieldRange {
+            field: field.to_owned(),
+            ..Default::default()
+        };
+
+        match range.start_bound() {
+            Bound::Included(v) => field_range.gte = Some(v.clone().into()),
+            Bound::Excluded(v) => field_range.gt = Some(v.clone().into()),
+            Bound::Unbounded => (),
+        }
+
+        match range.end_bound() {
+            Bound::Included(v) => field_range.lte = Some(v.clone().into()),
+            Bound::Excluded(v) => field_range.lt = Some(v.clone().into()),
+            Bound::Unbounded => (),
+        }
+
+        field_range
+    }
+
+    pub(crate) fn proto(&self) -> Result<MixDbRangeFilter> {
+        let bound = |value: &Option<Value>| -> Result<String> {
+            match value {
+                Some(value) => Ok(serde_json::to_string(value)?),
+                None => Ok(String::new()),
+            }
+        };
+
+        Ok(MixDbRangeFilter {
+            field_name: self.field.clone(),
+            gt_json: bound(&self.gt)?,
+            gte_json: bound(&self.gte)?,
+            lt_json: bound(&self.lt)?,
+            lte_json: bound(&self.lte)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chained_and_or_flatten_and_double_not_cancels() {
+        let a = Filter::eq("tenant", "acme");
+        let b = Filter::exists("title");
+        let c = Filter::is_in("type", ["pdf", "html"]);
+
+        assert_eq!(
+            a.clone().and(b.clone()).and(c.clone()),
+            Filter::And(vec![a.clone(), b.clone(), c.clone()])
+        );
+        assert_eq!(
+            a.clone().or(b.clone()).or(c.clone()),
+            Filter::Or(vec![a.clone(), b.clone(), c.clone()])
+        );
+
+        // only a list of the same kind is extended
+        assert_eq!(
+            a.clone().and(b.clone()).or(c.clone()),
+            Filter::Or(vec![Filter::And(vec![a.clone(), b.clone()]), c])
+        );
+
+        assert_eq!(!a.clone(), Filter::Not(Box::new(a.clone())));
+        assert_eq!(!!a.clone(), a);
+    }
+
+    #[test]
+    fn filter_proto_encodes_values_as_json() {
+        let filter = Filter::eq("author.name", "Sam")
+            .and(Filter::range("year", 2000..=2010))
+            .and(!Filter::is_in("lang", ["de", "fr"]));
+
+        let field_value = |field: &str, value: &str| MixDbFilter {
+            filter: Some(mix_db_filter::Filter::Eq(MixDbFieldValue {
+                field_name: field.to_owned(),
+                value_json: value.to_owned(),
+            })),
+        };
+
+        let expected = MixDbFilter {
+            filter: Some(mix_db_filter::Filter::And(MixDbFilterList {
+                filters: vec![
+                    field_value("author.name", "\"Sam\""),
+                    MixDbFilter {
+                        filter: Some(mix_db_filter::Filter::Range(MixDbRangeFilter {
+                            field_name: "year".to_owned(),
+                            gte_json: "2000".to_owned(),
+                            lte_json: "2010".to_owned(),
+                            ..Default::default()
+                        })),
+                    },
+                    MixDbFilter {
+                        filter: Some(mix_db_filter::Filter::Not(Box::new(MixDbFilter {
+                            filter: Some(mix_db_filter::Filter::AnyOf(MixDbFieldValues {
+                                field_name: "lang".to_owned(),
+                                values_json: vec!["\"de\"".to_owned(), "\"fr\"".to_owned()],
+                            })),
+                        }))),
+                    },
+                ],
+            })),
+        };
+
+        assert_eq!(filter.proto().unwrap(), expected);
+        assert_eq!(filter_proto(None).unwrap(), None);
+    }
+}
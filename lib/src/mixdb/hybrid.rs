@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+
+/// How `MixDbCollection::hybrid_search` combines the vector and full text rankings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fusion {
+    /// Reciprocal rank fusion, a document scores `weight / (k + rank)` in each ranking it
+    /// appears in. Only ranks are used, so the two kinds of score never need to be comparable.
+    Rrf { k: f32, vector: f32, text: f32 },
+    /// Scores of each ranking are normalized to 0..=1 and summed with these weights
+    Weighted { vector: f32, text: f32 },
+}
+
+impl Default for Fusion {
+    fn default() -> Self {
+        Self::rrf()
+    }
+}
+
+impl Fusion {
+    /// Equally weighted reciprocal rank fusion with the usual `k` of 60
+    pub fn rrf() -> Self {
+        Fusion::Rrf {
+            k: 60.0,
+            vector: 1.0,
+            text: 1.0,
+        }
+    }
+
+    pub fn weighted(vector: f32, text: f32) -> Self {
+        Fusion::Weighted { vector, text }
+    }
+}
+
+/// Scales scores to 0..=1, all scores are 1 if they are equal
+fn normalize(ranking: &[(u32, f32)]) -> Vec<(u32, f32)> {
+    let min = ranking
+        .iter()
+        .map(|(_, s)| *s)
+        .fold(f32::INFINITY, f32::min);
+    let max = ranking
+        .iter()
+        .map(|(_, s)| *s)
+        .fold(f32::NEG_INFINITY, f32::max);
+
+    ranking
+        .iter()
+        .map(|(id, score)| {
+            let norm = if max > min {
+                (score - min) / (max - min)
+            } else {
+                1.0
+            };
+            (*id, norm)
+        })
+        .collect()
+}
+
+/// Fuses two rankings of document ids, best first, into one ranking of the best `limit`
+/// documents. Each ranking must hold a document at most once. Ties keep the document that
+/// ranked first in either list.
+pub(crate) fn fuse(
+    vector: &[(u32, f32)],
+    text: &[(u32, f32)],
+    fusion: Fusion,
+    limit: usize,
+) -> Vec<(u32, f32)> {
+    let scored: Vec<(Vec<(u32, f32)>, f32)> = match fusion {
+        Fusion::Rrf {
+            k,
+            vector: vector_weight,
+            text: text_weight,
+        } => {
+            let rrf = |ranking: &[(u32, f32)]| {
+                ranking
+                    .iter()
+                    .enumerate()
+                    .map(|(rank, (id, _))| (*id, 1.0 / (k + rank as f32 + 1.0)))
+                    .collect()
+            };
+
+            vec![(rrf(vector), vector_weight), (rrf(text), text_weight)]
+        }
+        Fusion::Weighted {
+            vector: vector_weight,
+            text: text_weight,
+        } => vec![
+            (normalize(vector), vector_weight),
+            (normalize(text), text_weight),
+        ],
+    };
+
+    // (score, best rank in any list) per document
+    let mut fused: HashMap<u32, (f32, usize)> = HashMap::new();
+
+    for (ranking, weight) in scored {
+        for (rank, (id, score)) in ranking.into_iter().enumerate() {
+            let entry = fused.entry(id).or_insert((0.0, rank));
+            entry.0 += weight * score;
+            entry.1 = entry.1.min(rank);
+        }
+    }
+
+    let mut fused: Vec<(u32, f32, usize)> = fused
+        .into_iter()
+        .map(|(id, (score, rank))| (id, score, rank))
+        .collect();
+
+    fused.sort_by(|a, b| {
+        b.1.partial_cmp(&a.1)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(a.2.cmp(&b.2))
+            .then(a.0.cmp(&b.0))
+    });
+
+    fused
+        .into_iter()
+        .take(limit)
+        .map(|(id, score, _)| (id, score))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rrf_and_weighted_fusion_rank_documents_found_by_both_first() {
+        let vector = [(2, 0.9), (1, 0.8), (3, 0.1)];
+        let text = [(3, 12.0), (2, 7.5), (4, 1.0)];
+
+        let ids = |fused: Vec<(u32, f32)>| fused.into_iter().map(|(id, _)| id).collect::<Vec<_>>();
+
+        assert_eq!(
+            ids(fuse(&vector, &text, Fusion::rrf(), 10)),
+            vec![2, 3, 1, 4]
+        );
+
+        // normalized: vector 2 -> 1.0, 1 -> 0.875, 3 -> 0.0, text 3 -> 1.0, 2 -> 0.59, 4 -> 0.0
+        assert_eq!(
+            ids(fuse(&vector, &text, Fusion::weighted(1.0, 1.0), 10)),
+            vec![2, 3, 1, 4]
+        );
+
+        // weighting text heavily favours the full text ranking
+        assert_eq!(
+            ids(fuse(&vector, &text, Fusion::weighted(0.1, 1.0), 2)),
+            vec![3, 2]
+        );
+    }
+}
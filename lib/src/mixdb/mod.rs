@@ -0,0 +1,732 @@
+use log::debug;
+use mixlayer_data::{Frame, JsonObject};
+use mixlayer_graph::{MxlNode, MxlNodeCtx, MxlSink};
+use mixlayer_runtime_ffi::{
+    prost::Message,
+    protos::{
+        MixDbCreateCollectionProto, MixDbCreateSearchIndex, MixDbCreateVectorIndex,
+        MixDbFinishVectorIndex, MixDbInsertProto, MixDbInsertVector, MixDbInsertVectorBatch,
+        MixDbSearchField, MixDbSearchFieldType, MixDbSearchFinishIndex, MixDbSearchIndexDocument,
+        MixDbWriteMode, MixDbWriteProto, MixDbWriteResult,
+    },
+    ByteBuffer,
+};
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::ai::{estimate_tokens, EmbeddingModel};
+
+mod cache;
+mod filter;
+mod hybrid;
+mod query;
+mod schema;
+mod source;
+
+pub use cache::MixDbEmbeddingCache;
+pub use filter::{FieldRange, Filter};
+pub use hybrid::Fusion;
+pub use query::{
+    Facet, FacetCount, HybridSearchHit, MixDb, MixDbCollection, SearchHit, SearchRequest,
+    SearchResults, Snippet, VectorQuery, VectorSearchHit,
+};
+pub use schema::{FieldType, MixDbSchema, Schema, SchemaField};
+pub use source::MxlCollectionSource;
+
+pub use mixlayer_macros::MixDbSchema;
+
+pub trait IntoChunks {
+    fn into_chunks(self) -> Vec<String>;
+}
+
+impl IntoChunks for String {
+    fn into_chunks(self) -> Vec<String> {
+        vec![self]
+    }
+}
+
+impl IntoChunks for Vec<String> {
+    fn into_chunks(self) -> Vec<String> {
+        self
+    }
+}
+
+extern "C" {
+    /// creates a collection and returns a handle to it
+    fn _mixdb_create_coll(cmd: *const ByteBuffer) -> ();
+
+    /// inserts a document into a collection by handle, returns document id
+    fn _mixdb_insert(cmd: *const ByteBuffer) -> i64;
+
+    /// writes a document keyed by the collection's id field
+    // proto: MixDbWriteProto -> MixDbWriteResult
+    fn _mixdb_write(cmd: *const ByteBuffer) -> *mut ByteBuffer;
+
+    fn _mixdb_create_fts_index(cmd: *const ByteBuffer) -> u32;
+    fn _mixdb_insert_fts_index(cmd: *const ByteBuffer) -> u32;
+
+    fn _mixdb_create_vector_index(cmd: *const ByteBuffer) -> ();
+    // proto: MixDbInsertVectorBatch
+    fn _mixdb_insert_vector_batch(cmd: *const ByteBuffer) -> ();
+    fn _mixdb_finish_vector_index(cmd: *const ByteBuffer) -> ();
+
+    fn _mxl_embed_data(cmd: *const ByteBuffer) -> *const ByteBuffer;
+
+    fn _mixdb_create_search_index(cmd: *const ByteBuffer) -> *const ByteBuffer;
+    fn _mixdb_search_index_insert(cmd: *const ByteBuffer) -> *const ByteBuffer;
+    fn _mixdb_search_index_finish(cmd: *const ByteBuffer) -> *const ByteBuffer;
+}
+
+/// Sends a request proto to the host and decodes the response proto it returns
+fn host_request<Req: Message, Resp: Message + Default>(
+    request: Req,
+    extern_fn: unsafe extern "C" fn(*const ByteBuffer) -> *mut ByteBuffer,
+) -> Result<Resp> {
+    let request_bytes: ByteBuffer = request.encode_to_vec().into();
+
+    let response_bytes = unsafe {
+        let buf = extern_fn(&request_bytes);
+
+        if buf.is_null() {
+            return Err(anyhow!("mixdb returned no response"));
+        }
+
+        Box::from_raw(buf)
+    };
+
+    Ok(Resp::decode(response_bytes.into_bytes())?)
+}
+
+fn parse_document(json: &str) -> Result<JsonObject> {
+    let value: serde_json::Value =
+        serde_json::from_str(json).context("error parsing mixdb document")?;
+    JsonObject::try_from(value)
+}
+
+/// Name of the index used when none is given
+pub const DEFAULT_INDEX: &str = "default";
+
+/// A chunk of a document and its embedding, ready to be inserted into a vector index
+#[derive(Debug, PartialEq)]
+struct EmbeddedChunk {
+    doc_id: u32,
+    chunk: String,
+    vector: Vec<f32>,
+}
+
+/// Queues chunks across documents and embeds them in batches within the model's limits
+struct ChunkBatcher {
+    embedding_model: Box<dyn EmbeddingModel + Send + Sync>,
+
+    /// (document id, chunk) waiting to be embedded in the next batch
+    pending: Vec<(u32, String)>,
+    pending_tokens: u32,
+}
+
+impl ChunkBatcher {
+    fn new(embedding_model: Box<dyn EmbeddingModel + Send + Sync>) -> Self {
+        Self {
+            embedding_model,
+            pending: Vec::new(),
+            pending_tokens: 0,
+        }
+    }
+
+    /// Queues a chunk, embedding the queued chunks first if adding it would go over the
+    /// model's batch limits. Embedded batches are passed to `insert`.
+    fn push<F>(&mut self, doc_id: u32, chunk: String, mut insert: F) -> Result<()>
+    where
+        F: FnMut(Vec<EmbeddedChunk>),
+    {
+        let tokens = estimate_tokens(&chunk);
+        let max_batch_size = self.embedding_model.batch_size_limit().max(1);
+        let max_batch_tokens = self.embedding_model.batch_token_limit();
+
+        if !self.pending.is_empty()
+            && (self.pending.len() >= max_batch_size
+                || self.pending_tokens.saturating_add(tokens) > max_batch_tokens)
+        {
+            insert(self.flush()?);
+        }
+
+        self.pending.push((doc_id, chunk));
+        self.pending_tokens = self.pending_tokens.saturating_add(tokens);
+
+        if self.pending.len() >= max_batch_size {
+            insert(self.flush()?);
+        }
+
+        Ok(())
+    }
+
+    /// Forgets the queued chunks of a document, e.g. once a newer version replaced it
+    fn drop_document(&mut self, doc_id: u32) {
+        self.pending.retain(|(id, _)| *id != doc_id);
+        self.pending_tokens = self.pending.iter().fold(0, |tokens, (_, chunk)| {
+            tokens.saturating_add(estimate_tokens(chunk))
+        });
+    }
+
+    /// Embeds the queued chunks in one batch, returns nothing if none are queued
+    fn flush(&mut self) -> Result<Vec<EmbeddedChunk>> {
+        if self.pending.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let pending = std::mem::take(&mut self.pending);
+        self.pending_tokens = 0;
+
+        let texts: Vec<&str> = pending.iter().map(|(_, chunk)| chunk.as_str()).collect();
+        let embeddings = self.embedding_model.embed_batch(&texts)?;
+
+        if embeddings.len() != pending.len() {
+            return Err(anyhow!(
+                "embedding model returned {} vectors for {} chunks",
+                embeddings.len(),
+                pending.len()
+            ));
+        }
+
+        Ok(pending
+            .into_iter()
+            .zip(embeddings)
+            .map(|((doc_id, chunk), vector)| EmbeddedChunk {
+                doc_id,
+                chunk,
+                vector,
+            })
+            .collect())
+    }
+}
+
+struct MxlVectorConfig {
+    index_name: String,
+    chunk_fn: Box<dyn Fn(&JsonObject) -> Vec<String> + Send + Sync>,
+    batcher: ChunkBatcher,
+}
+
+impl MxlVectorConfig {
+    fn push_chunk(&mut self, coll_name: &str, doc_id: u32, chunk: String) -> Result<()> {
+        let index_name = &self.index_name;
+
+        self.batcher.push(doc_id, chunk, |batch| {
+            insert_vectors(coll_name, index_name, batch)
+        })
+    }
+
+    fn flush(&mut self, coll_name: &str) -> Result<()> {
+        let batch = self.batcher.flush()?;
+        insert_vectors(coll_name, &self.index_name, batch);
+        Ok(())
+    }
+}
+
+/// Inserts a batch of embedded chunks into a vector index in one call
+fn insert_vectors(coll_name: &str, index_name: &str, batch: Vec<EmbeddedChunk>) {
+    if batch.is_empty() {
+        return;
+    }
+
+    debug!(
+        "inserting {} vectors into index {} of collection {}",
+        batch.len(),
+        index_name,
+        coll_name
+    );
+
+    let vectors = batch
+        .into_iter()
+        .map(|embedded| MixDbInsertVector {
+            collection: coll_name.to_owned(),
+            index_name: index_name.to_owned(),
+            document_id: embedded.doc_id as i32,
+            chunk_text: embedded.chunk,
+            vector: embedded.vector,
+        })
+        .collect();
+
+    let insert_buf: ByteBuffer = MixDbInsertVectorBatch { vectors }.encode_to_vec().into();
+    unsafe { _mixdb_insert_vector_batch(&insert_buf) };
+}
+
+/// How `MxlCollectionSink` writes a document whose id is already in the collection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WriteMode {
+    /// Always adds a new document, so documents with the same id are duplicated
+    #[default]
+    Insert,
+    /// Merges the document's fields into the existing document
+    Upsert,
+    /// Overwrites the existing document
+    Replace,
+}
+
+impl From<WriteMode> for MixDbWriteMode {
+    fn from(mode: WriteMode) -> Self {
+        match mode {
+            WriteMode::Insert => MixDbWriteMode::WriteModeInsert,
+            WriteMode::Upsert => MixDbWriteMode::WriteModeUpsert,
+            WriteMode::Replace => MixDbWriteMode::WriteModeReplace,
+        }
+    }
+}
+
+pub struct MxlCollectionSink {
+    // coll_handle: u32,
+    coll_name: String,
+    id_field: String,
+    write_mode: WriteMode,
+
+    schema: Option<Schema>,
+
+    vector_configs: Vec<MxlVectorConfig>,
+    search_indexes: Vec<String>,
+}
+
+impl MxlCollectionSink {
+    pub fn new(name: &str, element_type: &str, id_field: &str) -> Self {
+        Self::create(name, element_type, id_field, None)
+    }
+
+    /// Creates a collection with the schema of `T`. Documents are validated against it before
+    /// they're written, and search indexes type their fields with it.
+    pub fn typed<T: MixDbSchema>(name: &str, id_field: &str) -> Self {
+        Self::create(
+            name,
+            std::any::type_name::<T>(),
+            id_field,
+            Some(T::schema()),
+        )
+    }
+
+    fn create(name: &str, element_type: &str, id_field: &str, schema: Option<Schema>) -> Self {
+        debug!("creating collection {}", name);
+
+        let create_proto = MixDbCreateCollectionProto {
+            db_name: "default".to_owned(),
+            collection: name.to_owned(),
+            element_type: element_type.to_owned(),
+            id_field: id_field.to_owned(),
+            schema: schema.as_ref().map(Schema::proto),
+        };
+
+        let create_buf: ByteBuffer = create_proto.encode_to_vec().into();
+        let _coll_handle = unsafe { _mixdb_create_coll(&create_buf) };
+
+        Self {
+            coll_name: name.to_owned(),
+            id_field: id_field.to_owned(),
+            write_mode: WriteMode::default(),
+            schema,
+            vector_configs: Vec::new(),
+            search_indexes: Vec::new(),
+        }
+    }
+
+    /// Sets how documents are written, upserts and replaces match documents on the
+    /// collection's id field so re-running a pipeline doesn't duplicate them
+    pub fn write_mode(&mut self, write_mode: WriteMode) -> Result<()> {
+        if write_mode != WriteMode::Insert && self.id_field.is_empty() {
+            return Err(anyhow!(
+                "{:?} needs the collection to have an id field",
+                write_mode
+            ));
+        }
+
+        self.write_mode = write_mode;
+
+        Ok(())
+    }
+
+    pub fn vector_index<E, F, C>(&mut self, embedding_model: E, chunk_fn: F) -> Result<()>
+    where
+        E: EmbeddingModel + Send + Sync + 'static,
+        C: IntoChunks,
+        F: Fn(&JsonObject) -> C + Send + Sync + 'static,
+    {
+        self.named_vector_index(DEFAULT_INDEX, embedding_model, chunk_fn)
+    }
+
+    /// Adds a vector index queries can select by name, each index chunks documents with its
+    /// own function and embeds them with its own model
+    pub fn named_vector_index<E, F, C>(
+        &mut self,
+        index_name: &str,
+        embedding_model: E,
+        chunk_fn: F,
+    ) -> Result<()>
+    where
+        E: EmbeddingModel + Send + Sync + 'static,
+        C: IntoChunks,
+        F: Fn(&JsonObject) -> C + Send + Sync + 'static,
+    {
+        if self
+            .vector_configs
+            .iter()
+            .any(|c| c.index_name == index_name)
+        {
+            return Err(anyhow!(
+                "collection already has vector index {}",
+                index_name
+            ));
+        }
+
+        let create_vec_idx = MixDbCreateVectorIndex {
+            db_name: "default".to_owned(),
+            collection: self.coll_name.clone(),
+            dimensions: embedding_model.num_dims() as i32,
+            index_name: index_name.to_owned(),
+        };
+
+        let create_buf: ByteBuffer = create_vec_idx.encode_to_vec().into();
+        let _coll_handle = unsafe { _mixdb_create_vector_index(&create_buf) };
+
+        let chunk_fn = move |chunk: &JsonObject| {
+            let chunks = (chunk_fn)(chunk).into_chunks();
+            chunks
+        };
+
+        self.vector_configs.push(MxlVectorConfig {
+            index_name: index_name.to_owned(),
+            chunk_fn: Box::new(chunk_fn),
+            batcher: ChunkBatcher::new(Box::new(embedding_model)),
+        });
+
+        Ok(())
+    }
+
+    pub fn search_index(&mut self, indexed_fields: &[&str]) -> Result<()> {
+        self.named_search_index(DEFAULT_INDEX, indexed_fields)
+    }
+
+    /// Adds a full text search index queries can select by name
+    pub fn named_search_index(&mut self, index_name: &str, indexed_fields: &[&str]) -> Result<()> {
+        if self.search_indexes.iter().any(|name| name == index_name) {
+            return Err(anyhow!(
+                "collection already has search index {}",
+                index_name
+            ));
+        }
+
+        let fields = indexed_fields
+            .iter()
+            .map(|s| MixDbSearchField {
+                field_name: s.to_string(),
+                // untyped fields are indexed as text
+                field_type: self
+                    .schema
+                    .as_ref()
+                    .and_then(|schema| schema.field(s))
+                    .map(|field| field.field_type.proto())
+                    .unwrap_or(MixDbSearchFieldType::SearchFieldText)
+                    as i32,
+            })
+            .collect();
+
+        let create_search_idx = MixDbCreateSearchIndex {
+            db_name: "default".to_owned(),
+            coll_name: self.coll_name.clone(),
+            index_name: index_name.to_owned(),
+            fields,
+        };
+
+        let create_buf: ByteBuffer = create_search_idx.encode_to_vec().into();
+        unsafe { _mixdb_create_search_index(&create_buf) };
+
+        self.search_indexes.push(index_name.to_owned());
+
+        Ok(())
+    }
+
+    /// Adds a document to the search indexes and queues its chunks for embedding, chunks are
+    /// embedded in batches across documents so vectors can lag behind the documents
+    fn index_frame(&mut self, doc_id: u32, document: &JsonObject) -> Result<()> {
+        for vector_config in self.vector_configs.iter_mut() {
+            let chunks = (vector_config.chunk_fn)(document);
+
+            for chunk in chunks {
+                vector_config.push_chunk(&self.coll_name, doc_id, chunk)?;
+            }
+        }
+
+        for index_name in self.search_indexes.iter() {
+            let insert_proto = MixDbSearchIndexDocument {
+                collection: self.coll_name.clone(),
+                index_name: index_name.clone(),
+                document_id: doc_id as i32,
+                json: serde_json::to_string(document.as_map()).context("error serializing json")?,
+            };
+
+            let insert_buf: ByteBuffer = insert_proto.encode_to_vec().into();
+            unsafe { _mixdb_search_index_insert(&insert_buf) };
+        }
+
+        Ok(())
+    }
+
+    fn finish_indexes(&mut self) -> Result<()> {
+        for vector_config in self.vector_configs.iter_mut() {
+            vector_config
+                .flush(&self.coll_name)
+                .context("error embedding chunks")?;
+        }
+
+        for vector_config in self.vector_configs.drain(..) {
+            let finish_proto = MixDbFinishVectorIndex {
+                collection: self.coll_name.clone(),
+                index_name: vector_config.index_name,
+            };
+
+            let finish_buf: ByteBuffer = finish_proto.encode_to_vec().into();
+            unsafe { _mixdb_finish_vector_index(&finish_buf) };
+        }
+
+        for index_name in self.search_indexes.drain(..) {
+            debug!("finalizing full text search index {}", index_name);
+            let finish_proto = MixDbSearchFinishIndex {
+                collection: self.coll_name.clone(),
+                index_name,
+            };
+
+            let finish_buf: ByteBuffer = finish_proto.encode_to_vec().into();
+            unsafe { _mixdb_search_index_finish(&finish_buf) };
+        }
+
+        Ok(())
+    }
+
+    /// Upserts or replaces a document by id, then indexes the document that was stored
+    fn write(&mut self, document: &JsonObject) -> Result<()> {
+        if !document.as_map().contains_key(&self.id_field) {
+            return Err(anyhow!("document has no {} field", self.id_field));
+        }
+
+        let write_proto = MixDbWriteProto {
+            db_name: "default".to_owned(),
+            collection: self.coll_name.clone(),
+            json: serde_json::to_string(document.as_map()).context("error serializing json")?,
+            mode: MixDbWriteMode::from(self.write_mode) as i32,
+        };
+
+        let result: MixDbWriteResult = host_request(write_proto, _mixdb_write)?;
+
+        if !result.error.is_empty() {
+            return Err(anyhow!("error writing document: {}", result.error));
+        }
+
+        debug!(
+            "wrote document {} into collection {} ({:?}, replaced: {})",
+            result.document_id, self.coll_name, self.write_mode, result.replaced
+        );
+
+        // the host dropped the index entries of a replaced document, so index it from scratch.
+        // Chunks of the old version that are still queued would be inserted after it.
+        let doc_id = result.document_id as u32;
+        for vector_config in self.vector_configs.iter_mut() {
+            vector_config.batcher.drop_document(doc_id);
+        }
+
+        let stored = parse_document(&result.document_json)?;
+        self.index_frame(doc_id, &stored)
+            .context("error indexing frame")
+    }
+}
+
+impl MxlNode for MxlCollectionSink {
+    fn tick(&mut self, ctx: &mut MxlNodeCtx) -> Result<()> {
+        let next = self.recv(ctx);
+
+        if let (Some(Frame::Data(data)), Some(schema)) = (&next, &self.schema) {
+            schema
+                .validate(data)
+                .with_context(|| format!("invalid document for collection {}", self.coll_name))?;
+        }
+
+        match &next {
+            Some(Frame::Data(data)) if self.write_mode != WriteMode::Insert => self.write(data)?,
+            Some(Frame::Data(data)) => {
+                let insert_proto = MixDbInsertProto {
+                    db_name: "default".to_owned(),
+                    collection: self.coll_name.clone(),
+                    json: serde_json::to_string(data.as_map()).unwrap(),
+                };
+
+                let insert_buf: ByteBuffer = insert_proto.encode_to_vec().into();
+                let doc_id = unsafe { _mixdb_insert(&insert_buf) };
+
+                //TODO make this better when we return better ffi errors
+                if doc_id < 0 {
+                    return Err(anyhow!("error inserting document"));
+                }
+
+                debug!(
+                    "inserted document {} into collection {}",
+                    doc_id, self.coll_name
+                );
+
+                self.index_frame(doc_id as u32, data)
+                    .context("error indexing frame")?;
+            }
+            _ => (),
+        }
+
+        Ok(())
+    }
+
+    fn on_finish(&mut self, _ctx: &mut MxlNodeCtx) -> Result<()> {
+        self.finish_indexes()
+            .with_context(|| "error finalizing indexes")
+    }
+
+    fn default_label(&self) -> Option<String> {
+        Some(format!("Collection {}", self.coll_name))
+    }
+}
+
+impl MxlSink for MxlCollectionSink {
+    type Input = JsonObject;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Embeds a chunk as its length, with the batch limits a test picks
+    struct FakeModel {
+        batch_size: usize,
+        batch_tokens: u32,
+    }
+
+    impl EmbeddingModel for FakeModel {
+        fn embed(&self, text: &str) -> Result<Vec<f32>> {
+            Ok(vec![text.len() as f32])
+        }
+
+        fn num_dims(&self) -> u32 {
+            1
+        }
+
+        fn token_limit(&self) -> u32 {
+            100
+        }
+
+        fn model_id(&self) -> &str {
+            "fake"
+        }
+
+        fn batch_size_limit(&self) -> usize {
+            self.batch_size
+        }
+
+        fn batch_token_limit(&self) -> u32 {
+            self.batch_tokens
+        }
+    }
+
+    /// Pushes `(doc id, chunk)`s and flushes like `on_finish`, returning the chunks of each
+    /// inserted batch
+    fn batches(model: FakeModel, chunks: &[(u32, &str)]) -> Vec<Vec<String>> {
+        let mut batcher = ChunkBatcher::new(Box::new(model));
+        let mut inserted = Vec::new();
+
+        let mut insert = |batch: Vec<EmbeddedChunk>| {
+            for embedded in batch.iter() {
+                assert_eq!(embedded.vector, vec![embedded.chunk.len() as f32]);
+            }
+            inserted.push(batch.into_iter().map(|e| e.chunk).collect::<Vec<_>>());
+        };
+
+        for (doc_id, chunk) in chunks {
+            batcher
+                .push(*doc_id, chunk.to_string(), &mut insert)
+                .unwrap();
+        }
+
+        let rest = batcher.flush().unwrap();
+        if !rest.is_empty() {
+            insert(rest);
+        }
+
+        inserted
+    }
+
+    #[test]
+    fn chunks_are_embedded_in_batches_within_the_model_limits() {
+        let by_size = FakeModel {
+            batch_size: 3,
+            batch_tokens: 1000,
+        };
+        let chunks: Vec<_> = ["a", "b", "c", "d", "e", "f", "g"]
+            .into_iter()
+            .enumerate()
+            .map(|(i, chunk)| (i as u32, chunk))
+            .collect();
+
+        // the last chunk is only embedded by the flush when the sink finishes
+        assert_eq!(
+            batches(by_size, &chunks),
+            vec![vec!["a", "b", "c"], vec!["d", "e", "f"], vec!["g"]]
+        );
+
+        // 4 tokens a batch, a chunk is estimated at a token per 4 bytes
+        let by_tokens = FakeModel {
+            batch_size: 10,
+            batch_tokens: 4,
+        };
+        let oversized = "x".repeat(40);
+
+        assert_eq!(
+            batches(
+                by_tokens,
+                &[
+                    (1, "aaaaaaaa"),
+                    (1, "bbbbbbbb"),
+                    (2, "cccccccc"),
+                    (3, &oversized),
+                    (4, "dddd")
+                ]
+            ),
+            vec![
+                vec!["aaaaaaaa".to_owned(), "bbbbbbbb".to_owned()],
+                vec!["cccccccc".to_owned()],
+                // a chunk over the limit on its own is still embedded, alone
+                vec![oversized.clone()],
+                vec!["dddd".to_owned()],
+            ]
+        );
+    }
+
+    #[test]
+    fn dropped_document_chunks_are_not_embedded() {
+        let mut batcher = ChunkBatcher::new(Box::new(FakeModel {
+            batch_size: 10,
+            batch_tokens: 4,
+        }));
+
+        let mut inserted = Vec::new();
+        batcher
+            .push(1, "aaaaaaaa".to_owned(), |b| inserted.push(b))
+            .unwrap();
+        batcher
+            .push(2, "bbbb".to_owned(), |b| inserted.push(b))
+            .unwrap();
+        batcher.drop_document(1);
+
+        // the dropped chunk's tokens no longer count towards the batch
+        batcher
+            .push(3, "cccccccc".to_owned(), |b| inserted.push(b))
+            .unwrap();
+        assert!(inserted.is_empty());
+
+        let chunks: Vec<_> = batcher
+            .flush()
+            .unwrap()
+            .into_iter()
+            .map(|e| (e.doc_id, e.chunk))
+            .collect();
+        assert_eq!(
+            chunks,
+            vec![(2, "bbbb".to_owned()), (3, "cccccccc".to_owned())]
+        );
+    }
+}
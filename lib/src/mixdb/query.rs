@@ -0,0 +1,502 @@
+use std::collections::{hash_map::Entry, HashMap};
+use std::ops::RangeBounds;
+
+use anyhow::{anyhow, Context, Result};
+use mixlayer_data::JsonObject;
+use mixlayer_runtime_ffi::{
+    protos::{
+        MixDbDeleteProto, MixDbDeleteResult, MixDbSearchRequest, MixDbSearchResponse,
+        MixDbVectorSearchRequest, MixDbVectorSearchResponse,
+    },
+    ByteBuffer,
+};
+
+use super::filter::{filter_proto, Filter};
+use super::hybrid::{fuse, Fusion};
+use super::{host_request, parse_document, DEFAULT_INDEX};
+use crate::ai::EmbeddingModel;
+
+/// Number of candidates `hybrid_search` fetches from each index for every hit it returns,
+/// vector hits are chunks so several can belong to one document
+const HYBRID_CANDIDATES_PER_HIT: usize = 4;
+
+extern "C" {
+    // proto: MixDbVectorSearchRequest -> MixDbVectorSearchResponse
+    fn _mixdb_vector_search(cmd: *const ByteBuffer) -> *mut ByteBuffer;
+
+    // proto: MixDbSearchRequest -> MixDbSearchResponse
+    fn _mixdb_search(cmd: *const ByteBuffer) -> *mut ByteBuffer;
+
+    // proto: MixDbDeleteProto -> MixDbDeleteResult
+    fn _mixdb_delete(cmd: *const ByteBuffer) -> *mut ByteBuffer;
+}
+
+/// A handle to a mixdb database, used to query the collections pipelines have written
+#[derive(Debug, Clone)]
+pub struct MixDb {
+    name: String,
+}
+
+impl Default for MixDb {
+    fn default() -> Self {
+        Self::new("default")
+    }
+}
+
+impl MixDb {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn collection(&self, name: &str) -> MixDbCollection {
+        MixDbCollection {
+            db_name: self.name.clone(),
+            name: name.to_owned(),
+            vector_index: DEFAULT_INDEX.to_owned(),
+            search_index: DEFAULT_INDEX.to_owned(),
+            embedding_model: None,
+        }
+    }
+}
+
+/// What a vector search looks for, text is embedded with the collection's embedding model
+#[derive(Debug, Clone)]
+pub enum VectorQuery {
+    Vector(Vec<f32>),
+    Text(String),
+}
+
+impl From<Vec<f32>> for VectorQuery {
+    fn from(vector: Vec<f32>) -> Self {
+        VectorQuery::Vector(vector)
+    }
+}
+
+impl From<String> for VectorQuery {
+    fn from(text: String) -> Self {
+        VectorQuery::Text(text)
+    }
+}
+
+impl From<&str> for VectorQuery {
+    fn from(text: &str) -> Self {
+        VectorQuery::Text(text.to_owned())
+    }
+}
+
+/// A chunk matched by a vector search and the document it was taken from
+#[derive(Debug, Clone)]
+pub struct VectorSearchHit {
+    pub document_id: u32,
+    /// similarity to the query, higher is closer
+    pub score: f32,
+    pub chunk_text: String,
+    pub document: JsonObject,
+}
+
+/// A full text query, see `MixDbCollection::search_with`
+#[derive(Debug, Clone)]
+pub struct SearchRequest {
+    query: String,
+    fields: Vec<String>,
+    limit: usize,
+    offset: usize,
+    filter: Option<Filter>,
+    facets: Vec<String>,
+    highlight: Option<(String, String)>,
+}
+
+impl SearchRequest {
+    /// Matches `query` against every indexed field and returns the best 10 hits
+    pub fn new(query: &str) -> Self {
+        Self {
+            query: query.to_owned(),
+            fields: Vec::new(),
+            limit: 10,
+            offset: 0,
+            filter: None,
+            facets: Vec::new(),
+            highlight: None,
+        }
+    }
+
+    /// Only matches the query against these fields
+    pub fn fields(mut self, fields: &[&str]) -> Self {
+        self.fields = fields.iter().map(|f| f.to_string()).collect();
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    pub fn offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Only returns documents matching `filter`, and-ed with any filter already set
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filter = Some(match self.filter.take() {
+            Some(prev) => prev.and(filter),
+            None => filter,
+        });
+        self
+    }
+
+    /// Only returns documents whose integer, float or date `field` is in `range`, e.g.
+    /// `.range("year", 2000..=2010)`
+    pub fn range<T, R>(self, field: &str, range: R) -> Self
+    where
+        T: Into<serde_json::Value> + Clone,
+        R: RangeBounds<T>,
+    {
+        self.filter(Filter::range(field, range))
+    }
+
+    /// Counts the values of a keyword, integer or boolean field across every matching
+    /// document, see `SearchResults::facets`
+    pub fn facet(mut self, field: &str) -> Self {
+        self.facets.push(field.to_owned());
+        self
+    }
+
+    /// Returns snippets of the matched fields with matched terms wrapped in `<b>` tags
+    pub fn highlight(self) -> Self {
+        self.highlight_with("<b>", "</b>")
+    }
+
+    /// Returns snippets of the matched fields with matched terms wrapped in the given tags
+    pub fn highlight_with(mut self, pre_tag: &str, post_tag: &str) -> Self {
+        self.highlight = Some((pre_tag.to_owned(), post_tag.to_owned()));
+        self
+    }
+}
+
+/// A highlighted fragment of a matched field
+#[derive(Debug, Clone)]
+pub struct Snippet {
+    pub field: String,
+    pub fragment: String,
+}
+
+/// A document matched by a full text search
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub document_id: u32,
+    /// BM25 score, higher is a better match
+    pub score: f32,
+    pub matched_fields: Vec<String>,
+    /// empty unless highlighting was requested
+    pub snippets: Vec<Snippet>,
+    pub document: JsonObject,
+}
+
+/// Number of matching documents with a value of a faceted field
+#[derive(Debug, Clone)]
+pub struct FacetCount {
+    pub value: serde_json::Value,
+    pub count: u64,
+}
+
+/// Value counts of a faceted field, most common first
+#[derive(Debug, Clone)]
+pub struct Facet {
+    pub field: String,
+    pub counts: Vec<FacetCount>,
+}
+
+/// A page of full text search hits, best first
+#[derive(Debug, Clone)]
+pub struct SearchResults {
+    /// number of documents matching the query, ignoring limit and offset
+    pub total_hits: u64,
+    pub hits: Vec<SearchHit>,
+    /// one per requested facet
+    pub facets: Vec<Facet>,
+}
+
+/// A document found by a hybrid search
+#[derive(Debug, Clone)]
+pub struct HybridSearchHit {
+    pub document_id: u32,
+    /// fused score, higher is a better match
+    pub score: f32,
+    /// similarity of the best matching chunk, if the vector search found the document
+    pub vector_score: Option<f32>,
+    /// BM25 score, if the full text search found the document
+    pub text_score: Option<f32>,
+    /// best matching chunk, if the vector search found the document
+    pub chunk_text: Option<String>,
+    pub document: JsonObject,
+}
+
+/// A handle to a collection in a mixdb database
+pub struct MixDbCollection {
+    db_name: String,
+    name: String,
+    vector_index: String,
+    search_index: String,
+    embedding_model: Option<Box<dyn EmbeddingModel + Send + Sync>>,
+}
+
+impl MixDbCollection {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Deletes the document whose id field is `id`, with its vector and search index entries.
+    /// Returns false if there was no such document.
+    pub fn delete(&self, id: impl Into<serde_json::Value>) -> Result<bool> {
+        let request = MixDbDeleteProto {
+            db_name: self.db_name.clone(),
+            collection: self.name.clone(),
+            id_json: serde_json::to_string(&id.into())?,
+        };
+
+        let result: MixDbDeleteResult = host_request(request, _mixdb_delete)?;
+
+        if !result.error.is_empty() {
+            return Err(anyhow!("delete failed: {}", result.error));
+        }
+
+        Ok(result.deleted)
+    }
+
+    /// Selects the vector index searched by `vector_search` and `hybrid_search`
+    pub fn vector_index(mut self, index_name: &str) -> Self {
+        self.vector_index = index_name.to_owned();
+        self
+    }
+
+    /// Selects the full text index searched by `search` and `hybrid_search`
+    pub fn search_index(mut self, index_name: &str) -> Self {
+        self.search_index = index_name.to_owned();
+        self
+    }
+
+    /// Sets the model text queries are embedded with, it should be the one the collection's
+    /// vector index was built with
+    pub fn embedding_model<E>(mut self, embedding_model: E) -> Self
+    where
+        E: EmbeddingModel + Send + Sync + 'static,
+    {
+        self.embedding_model = Some(Box::new(embedding_model));
+        self
+    }
+
+    /// Returns the `k` chunks nearest to the query, closest first. With a filter, only chunks
+    /// of documents matching it are returned.
+    pub fn vector_search(
+        &self,
+        query: impl Into<VectorQuery>,
+        k: usize,
+        filter: Option<&Filter>,
+    ) -> Result<Vec<VectorSearchHit>> {
+        let vector = match query.into() {
+            VectorQuery::Vector(vector) => vector,
+            VectorQuery::Text(text) => self
+                .embedding_model
+                .as_ref()
+                .ok_or_else(|| anyhow!("text queries need an embedding model"))?
+                .embed(&text)
+                .context("error embedding query")?,
+        };
+
+        let request = MixDbVectorSearchRequest {
+            db_name: self.db_name.clone(),
+            collection: self.name.clone(),
+            index_name: self.vector_index.clone(),
+            vector,
+            k: k as u32,
+            filter: filter_proto(filter)?,
+        };
+
+        let response: MixDbVectorSearchResponse = host_request(request, _mixdb_vector_search)?;
+
+        if !response.error.is_empty() {
+            return Err(anyhow!("vector search failed: {}", response.error));
+        }
+
+        response
+            .hits
+            .into_iter()
+            .map(|hit| {
+                Ok(VectorSearchHit {
+                    document_id: hit.document_id as u32,
+                    score: hit.score,
+                    chunk_text: hit.chunk_text,
+                    document: parse_document(&hit.document_json)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Full text search of the collection's search index, returns `limit` hits after skipping
+    /// `offset`. Matches every indexed field if `fields` is empty.
+    pub fn search(
+        &self,
+        query: &str,
+        fields: &[&str],
+        limit: usize,
+        offset: usize,
+    ) -> Result<SearchResults> {
+        self.search_with(
+            SearchRequest::new(query)
+                .fields(fields)
+                .limit(limit)
+                .offset(offset),
+        )
+    }
+
+    /// Full text search with filtering and highlighting
+    pub fn search_with(&self, search: SearchRequest) -> Result<SearchResults> {
+        let (highlight_pre_tag, highlight_post_tag) = search.highlight.clone().unwrap_or_default();
+
+        let request = MixDbSearchRequest {
+            db_name: self.db_name.clone(),
+            collection: self.name.clone(),
+            index_name: self.search_index.clone(),
+            query: search.query,
+            fields: search.fields,
+            limit: search.limit as u32,
+            offset: search.offset as u32,
+            filter: filter_proto(search.filter.as_ref())?,
+            highlight: search.highlight.is_some(),
+            highlight_pre_tag,
+            highlight_post_tag,
+            facets: search.facets,
+        };
+
+        let response: MixDbSearchResponse = host_request(request, _mixdb_search)?;
+
+        if !response.error.is_empty() {
+            return Err(anyhow!("search failed: {}", response.error));
+        }
+
+        let hits = response
+            .hits
+            .into_iter()
+            .map(|hit| {
+                Ok(SearchHit {
+                    document_id: hit.document_id as u32,
+                    score: hit.score,
+                    matched_fields: hit.matched_fields,
+                    snippets: hit
+                        .snippets
+                        .into_iter()
+                        .map(|s| Snippet {
+                            field: s.field_name,
+                            fragment: s.fragment,
+                        })
+                        .collect(),
+                    document: parse_document(&hit.document_json)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let facets = response
+            .facets
+            .into_iter()
+            .map(|facet| {
+                let counts = facet
+                    .counts
+                    .into_iter()
+                    .map(|c| {
+                        Ok(FacetCount {
+                            value: serde_json::from_str(&c.value_json)
+                                .context("error parsing facet value")?,
+                            count: c.count,
+                        })
+                    })
+                    .collect::<Result<_>>()?;
+
+                Ok(Facet {
+                    field: facet.field_name,
+                    counts,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(SearchResults {
+            total_hits: response.total_hits,
+            hits,
+            facets,
+        })
+    }
+
+    /// Runs a vector search and a full text search for `text` and fuses the two rankings into
+    /// the best `k` documents. Needs both a vector and a search index on the collection, and an
+    /// embedding model to embed `text`. With a filter, both searches only return documents
+    /// matching it.
+    pub fn hybrid_search(
+        &self,
+        text: &str,
+        k: usize,
+        filter: Option<&Filter>,
+        fusion: Fusion,
+    ) -> Result<Vec<HybridSearchHit>> {
+        let candidates = k.saturating_mul(HYBRID_CANDIDATES_PER_HIT);
+
+        let mut vector_ranking = Vec::new();
+        let mut vector_hits: HashMap<u32, VectorSearchHit> = HashMap::new();
+
+        // keep the best chunk of each document, hits are sorted closest first
+        for hit in self.vector_search(text, candidates, filter)? {
+            if let Entry::Vacant(entry) = vector_hits.entry(hit.document_id) {
+                vector_ranking.push((hit.document_id, hit.score));
+                entry.insert(hit);
+            }
+        }
+
+        let mut text_ranking = Vec::new();
+        let mut text_hits: HashMap<u32, SearchHit> = HashMap::new();
+
+        let mut search = SearchRequest::new(text).limit(candidates);
+        if let Some(filter) = filter {
+            search = search.filter(filter.clone());
+        }
+
+        for hit in self.search_with(search)?.hits {
+            text_ranking.push((hit.document_id, hit.score));
+            text_hits.insert(hit.document_id, hit);
+        }
+
+        let hits = fuse(&vector_ranking, &text_ranking, fusion, k)
+            .into_iter()
+            .map(|(document_id, score)| {
+                let vector_hit = vector_hits.remove(&document_id);
+                let text_hit = text_hits.remove(&document_id);
+
+                let (vector_score, chunk_text) = match &vector_hit {
+                    Some(hit) => (Some(hit.score), Some(hit.chunk_text.clone())),
+                    None => (None, None),
+                };
+
+                let document = match (vector_hit, &text_hit) {
+                    (Some(hit), _) => hit.document,
+                    (None, Some(hit)) => hit.document.clone(),
+                    (None, None) => unreachable!("fused documents come from one of the rankings"),
+                };
+
+                HybridSearchHit {
+                    document_id,
+                    score,
+                    vector_score,
+                    text_score: text_hit.map(|hit| hit.score),
+                    chunk_text,
+                    document,
+                }
+            })
+            .collect();
+
+        Ok(hits)
+    }
+}
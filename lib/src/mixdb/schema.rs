@@ -0,0 +1,229 @@
+use anyhow::{anyhow, Result};
+use mixlayer_data::JsonObject;
+use mixlayer_runtime_ffi::protos::{MixDbSchemaField, MixDbSchemaProto, MixDbSearchFieldType};
+use serde_json::Value;
+
+/// Type of a collection field, decides how search indexes store and match it
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldType {
+    /// Tokenized for full text search
+    Text,
+    /// A string matched as a whole, e.g. an id or a category, can be faceted
+    Keyword,
+    Integer,
+    Float,
+    /// An RFC 3339 string or milliseconds since the unix epoch
+    Date,
+    Boolean,
+    /// An object with its own fields
+    Nested(Vec<SchemaField>),
+}
+
+impl FieldType {
+    pub(crate) fn proto(&self) -> MixDbSearchFieldType {
+        match self {
+            FieldType::Text => MixDbSearchFieldType::SearchFieldText,
+            FieldType::Keyword => MixDbSearchFieldType::SearchFieldKeyword,
+            FieldType::Integer => MixDbSearchFieldType::SearchFieldInteger,
+            FieldType::Float => MixDbSearchFieldType::SearchFieldFloat,
+            FieldType::Date => MixDbSearchFieldType::SearchFieldDate,
+            FieldType::Boolean => MixDbSearchFieldType::SearchFieldBoolean,
+            FieldType::Nested(_) => MixDbSearchFieldType::SearchFieldNested,
+        }
+    }
+
+    /// Checks `value` is of this type, the fields of nested objects are checked as well
+    fn check(&self, value: &Value, path: &str) -> Result<()> {
+        let ok = match (self, value) {
+            (FieldType::Text | FieldType::Keyword, Value::String(_)) => true,
+            (FieldType::Integer, Value::Number(n)) => n.is_i64() || n.is_u64(),
+            (FieldType::Float, Value::Number(_)) => true,
+            (FieldType::Date, Value::Number(n)) => n.is_i64() || n.is_u64(),
+            (FieldType::Date, Value::String(s)) => is_rfc3339_date(s),
+            (FieldType::Boolean, Value::Bool(_)) => true,
+            (FieldType::Nested(fields), Value::Object(map)) => {
+                return validate_fields(fields, map, &format!("{}.", path));
+            }
+            _ => false,
+        };
+
+        if ok {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "field {} should be {}, found {}",
+                path,
+                self.describe(),
+                value
+            ))
+        }
+    }
+
+    fn describe(&self) -> &'static str {
+        match self {
+            FieldType::Text | FieldType::Keyword => "a string",
+            FieldType::Integer => "an integer",
+            FieldType::Float => "a number",
+            FieldType::Date => "an RFC 3339 date or epoch milliseconds",
+            FieldType::Boolean => "a boolean",
+            FieldType::Nested(_) => "an object",
+        }
+    }
+}
+
+/// Checks for a `YYYY-MM-DD` date optionally followed by a time, the host parses it fully
+fn is_rfc3339_date(s: &str) -> bool {
+    let b = s.as_bytes();
+
+    b.len() >= 10
+        && b[..4].iter().all(u8::is_ascii_digit)
+        && b[4] == b'-'
+        && b[5..7].iter().all(u8::is_ascii_digit)
+        && b[7] == b'-'
+        && b[8..10].iter().all(u8::is_ascii_digit)
+        && (b.len() == 10 || b[10] == b'T' || b[10] == b't' || b[10] == b' ')
+}
+
+/// A field of a collection schema
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaField {
+    pub name: String,
+    pub field_type: FieldType,
+    /// documents must have the field and it can't be null
+    pub required: bool,
+    /// the field holds an array of values
+    pub repeated: bool,
+}
+
+impl SchemaField {
+    pub fn new(name: &str, field_type: FieldType) -> Self {
+        Self {
+            name: name.to_owned(),
+            field_type,
+            required: true,
+            repeated: false,
+        }
+    }
+
+    pub fn optional(mut self) -> Self {
+        self.required = false;
+        self
+    }
+
+    pub fn repeated(mut self) -> Self {
+        self.repeated = true;
+        self
+    }
+
+    fn proto(&self) -> MixDbSchemaField {
+        let fields = match &self.field_type {
+            FieldType::Nested(fields) => fields.iter().map(SchemaField::proto).collect(),
+            _ => Vec::new(),
+        };
+
+        MixDbSchemaField {
+            field_name: self.name.clone(),
+            field_type: self.field_type.proto() as i32,
+            required: self.required,
+            repeated: self.repeated,
+            fields,
+        }
+    }
+}
+
+fn validate_fields(
+    fields: &[SchemaField],
+    map: &serde_json::Map<String, Value>,
+    prefix: &str,
+) -> Result<()> {
+    for field in fields {
+        let path = format!("{}{}", prefix, field.name);
+
+        let value = match map.get(&field.name) {
+            None | Some(Value::Null) if field.required => {
+                return Err(anyhow!("missing required field {}", path))
+            }
+            None | Some(Value::Null) => continue,
+            Some(value) => value,
+        };
+
+        match (field.repeated, value) {
+            (true, Value::Array(values)) => {
+                for (idx, value) in values.iter().enumerate() {
+                    field
+                        .field_type
+                        .check(value, &format!("{}[{}]", path, idx))?;
+                }
+            }
+            (true, value) => {
+                return Err(anyhow!(
+                    "field {} should be an array, found {}",
+                    path,
+                    value
+                ))
+            }
+            (false, value) => field.field_type.check(value, &path)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// The fields of a collection's documents. Documents may have fields the schema doesn't
+/// mention, they are stored but can't be typed in indexes.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Schema {
+    pub fields: Vec<SchemaField>,
+}
+
+impl Schema {
+    pub fn new(fields: Vec<SchemaField>) -> Self {
+        Self { fields }
+    }
+
+    /// Looks up a field by name, fields of nested objects are separated by dots, e.g.
+    /// `author.name`
+    pub fn field(&self, path: &str) -> Option<&SchemaField> {
+        let mut fields = &self.fields;
+        let mut parts = path.split('.').peekable();
+
+        while let Some(part) = parts.next() {
+            let field = fields.iter().find(|f| f.name == part)?;
+
+            if parts.peek().is_none() {
+                return Some(field);
+            }
+
+            match &field.field_type {
+                FieldType::Nested(nested) => fields = nested,
+                _ => return None,
+            }
+        }
+
+        None
+    }
+
+    /// Checks that the document has every required field and that fields hold values of
+    /// their type
+    pub fn validate(&self, document: &JsonObject) -> Result<()> {
+        validate_fields(&self.fields, document.as_map(), "")
+    }
+
+    pub(crate) fn proto(&self) -> MixDbSchemaProto {
+        MixDbSchemaProto {
+            fields: self.fields.iter().map(SchemaField::proto).collect(),
+        }
+    }
+}
+
+/// A type whose fields describe a collection schema, usually derived with
+/// `#[derive(MixDbSchema)]`.
+///
+/// The derive infers field types from Rust types: strings are `Text`, integers `Integer`,
+/// floats `Float` and `bool` is `Boolean`. `Option` fields are optional and `Vec` fields are
+/// repeated. Other types need an attribute: `#[mixdb(keyword)]`, `#[mixdb(date)]`,
+/// `#[mixdb(nested)]` for types that implement `MixDbSchema` themselves, and so on. Fields can be
+/// renamed with `#[mixdb(rename = "...")]` or left out with `#[mixdb(skip)]`.
+pub trait MixDbSchema {
+    fn schema() -> Schema;
+}
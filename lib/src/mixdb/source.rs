@@ -0,0 +1,224 @@
+use std::collections::VecDeque;
+
+use anyhow::{anyhow, Result};
+use mixlayer_data::{Frame, JsonObject};
+use mixlayer_graph::{MxlNode, MxlNodeCtx, MxlSource};
+use mixlayer_runtime_ffi::{
+    prost::Message,
+    protos::{MixDbCollIteratorBatch, MixDbCollIteratorRequest},
+    ByteBuffer,
+};
+
+use super::filter::{filter_proto, Filter};
+use super::{parse_document, MixDb};
+
+extern "C" {
+    /// opens an iterator over a collection, returns its handle or a negative value on error
+    fn _mixdb_coll_iterator(cmd: *const ByteBuffer) -> i64;
+
+    /// returns the next batch of documents in the iterator
+    // proto: MixDbCollIteratorBatch
+    fn _mixdb_coll_iterator_next(iter_handle: u32) -> *mut ByteBuffer;
+
+    fn _mixdb_coll_iterator_close(iter_handle: u32) -> ();
+}
+
+const DEFAULT_BATCH_SIZE: usize = 100;
+
+/// Reads the documents of a mixdb collection, fetching them from the host in batches
+pub struct MxlCollectionSource {
+    db_name: String,
+    coll_name: String,
+    filter: Option<Filter>,
+    fields: Vec<String>,
+    batch_size: usize,
+
+    iter_handle: Option<u32>,
+    /// set once opening or reading the iterator failed, the source ends instead of retrying
+    failed: bool,
+    batch: VecDeque<JsonObject>,
+    done: bool,
+}
+
+impl MxlCollectionSource {
+    pub fn new(db: &MixDb, collection: &str) -> Self {
+        Self {
+            db_name: db.name().to_owned(),
+            coll_name: collection.to_owned(),
+            filter: None,
+            fields: Vec::new(),
+            batch_size: DEFAULT_BATCH_SIZE,
+            iter_handle: None,
+            failed: false,
+            batch: VecDeque::new(),
+            done: false,
+        }
+    }
+
+    /// Only reads documents matching `filter`, and any filter set before
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filter = Some(match self.filter.take() {
+            Some(prev) => prev.and(filter),
+            None => filter,
+        });
+        self
+    }
+
+    /// Only returns these fields of each document
+    pub fn fields(mut self, fields: &[&str]) -> Self {
+        self.fields = fields.iter().map(|f| f.to_string()).collect();
+        self
+    }
+
+    /// Number of documents fetched from the host at a time
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    fn open(&mut self) -> Result<u32> {
+        let request = MixDbCollIteratorRequest {
+            db_name: self.db_name.clone(),
+            collection: self.coll_name.clone(),
+            filter: filter_proto(self.filter.as_ref())?,
+            fields: self.fields.clone(),
+            batch_size: self.batch_size as u32,
+        };
+
+        let request_buf: ByteBuffer = request.encode_to_vec().into();
+        let handle = unsafe { _mixdb_coll_iterator(&request_buf) };
+
+        if handle < 0 {
+            return Err(anyhow!(
+                "error opening iterator over collection {}",
+                self.coll_name
+            ));
+        }
+
+        Ok(handle as u32)
+    }
+
+    fn next_batch(&mut self, iter_handle: u32) -> Result<()> {
+        let batch_buf = unsafe {
+            let buf = _mixdb_coll_iterator_next(iter_handle);
+
+            if buf.is_null() {
+                return Err(anyhow!("mixdb returned no batch"));
+            }
+
+            Box::from_raw(buf)
+        };
+
+        let batch = MixDbCollIteratorBatch::decode(batch_buf.into_bytes())?;
+
+        if !batch.error.is_empty() {
+            return Err(anyhow!(
+                "error reading collection {}: {}",
+                self.coll_name,
+                batch.error
+            ));
+        }
+
+        // parse the whole batch first so a bad document doesn't leave part of it queued
+        let documents = batch
+            .documents
+            .iter()
+            .map(|document| parse_document(&document.json))
+            .collect::<Result<Vec<_>>>()?;
+
+        self.batch.extend(documents);
+
+        self.done = batch.done;
+
+        Ok(())
+    }
+
+    fn close(&mut self) {
+        if let Some(iter_handle) = self.iter_handle.take() {
+            unsafe { _mixdb_coll_iterator_close(iter_handle) };
+        }
+    }
+
+    /// Reads every matching document outside of a graph
+    pub(crate) fn read_all(mut self) -> Result<Vec<JsonObject>> {
+        let iter_handle = self.open()?;
+        self.iter_handle = Some(iter_handle);
+
+        let result = (|| {
+            while !self.done {
+                self.next_batch(iter_handle)?;
+            }
+            Ok(self.batch.drain(..).collect())
+        })();
+
+        self.close();
+
+        result
+    }
+}
+
+impl Drop for MxlCollectionSource {
+    fn drop(&mut self) {
+        // the graph may be dropped before the source finishes
+        self.close();
+    }
+}
+
+impl MxlSource for MxlCollectionSource {
+    type Output = JsonObject;
+}
+
+impl MxlNode for MxlCollectionSource {
+    fn tick(&mut self, ctx: &mut MxlNodeCtx) -> Result<()> {
+        if self.failed {
+            // a failing tick doesn't finish the node, so end on the next one
+            ctx.stop();
+            return Ok(());
+        }
+
+        let iter_handle = match self.iter_handle {
+            Some(iter_handle) => iter_handle,
+            None => match self.open() {
+                Ok(iter_handle) => {
+                    self.iter_handle = Some(iter_handle);
+                    iter_handle
+                }
+                Err(err) => return self.fail(ctx, err),
+            },
+        };
+
+        if self.batch.is_empty() && !self.done {
+            if let Err(err) = self.next_batch(iter_handle) {
+                return self.fail(ctx, err);
+            }
+        }
+
+        match self.batch.pop_front() {
+            Some(document) => self.send(ctx, Frame::Data(document))?,
+            None if self.done => self.send(ctx, Frame::End)?,
+            None => (),
+        }
+
+        Ok(())
+    }
+
+    fn on_finish(&mut self, _ctx: &mut MxlNodeCtx) -> Result<()> {
+        // also reached when downstream closes before the collection is read to the end
+        self.close();
+        Ok(())
+    }
+
+    fn default_label(&self) -> Option<String> {
+        Some(format!("Collection {}", self.coll_name))
+    }
+}
+
+impl MxlCollectionSource {
+    /// Sends the error downstream and stops the source, the host isn't asked again
+    fn fail(&mut self, ctx: &mut MxlNodeCtx, err: anyhow::Error) -> Result<()> {
+        self.failed = true;
+        self.send(ctx, Frame::Error)?;
+        ctx.stop();
+        Err(err)
+    }
+}
@@ -1,39 +1,51 @@
-use crate::graph::{MxlNode, MxlNodeCtx, MxlSink};
+use crate::graph::{Emitter, OperatorNode, StreamOperator};
 use crate::io::MxlFile;
-use crate::Frame;
 use anyhow::Result;
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
+/// Writes each input string as a line to a file on the local filesystem
 pub struct FsLineSink {
     file: MxlFile,
+    path: PathBuf,
 }
 
 impl FsLineSink {
+    /// Opens `path` for writing
     pub fn new<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref().to_owned();
+
         Ok(Self {
-            file: MxlFile::open(path, crate::io::MxlFileMode::Write)?,
+            file: MxlFile::open(&path, crate::io::MxlFileMode::Write)?,
+            path,
         })
     }
+
+    /// Opens `path` for writing and returns the sink wrapped in an `OperatorNode`, ready to be
+    /// added to a graph
+    pub fn node<P: AsRef<Path>>(path: P) -> Result<OperatorNode<Self>> {
+        Ok(OperatorNode::new(Self::new(path)?))
+    }
 }
 
-impl MxlNode for FsLineSink {
-    fn tick(&mut self, ctx: &mut MxlNodeCtx) -> Result<()> {
-        let next = self.recv(ctx);
+impl StreamOperator for FsLineSink {
+    type Input = String;
+    type Output = ();
 
-        match next {
-            Some(Frame::Data(data)) => {
-                self.file
-                    .write_all(data.as_bytes())
-                    .and_then(|_| self.file.write_all("\n".as_bytes()))?;
-            }
-            _ => (),
-        }
+    fn on_element(&mut self, element: String, _out: &mut Emitter<()>) -> Result<()> {
+        self.file
+            .write_all(element.as_bytes())
+            .and_then(|_| self.file.write_all("\n".as_bytes()))?;
 
         Ok(())
     }
-}
 
-impl MxlSink for FsLineSink {
-    type Input = String;
+    fn on_end_of_input(&mut self, _port: u32, _out: &mut Emitter<()>) -> Result<()> {
+        self.file.flush()?;
+        Ok(())
+    }
+
+    fn label(&self) -> Option<String> {
+        Some(format!("{}", self.path.display()))
+    }
 }
@@ -25,15 +25,16 @@ impl<V: Debug + MxlData> MxlNode for DebugSink<V> {
                 Frame::Data(d) => debug!("frame: {:#?}", d),
                 Frame::End => debug!("single input finished"),
             }
-
-            if ctx.recv_finished() {
-                debug!("all inputs finished");
-            }
         }
 
         Ok(())
     }
 
+    fn on_finish(&mut self, _ctx: &mut MxlNodeCtx) -> Result<()> {
+        debug!("all inputs finished");
+        Ok(())
+    }
+
     fn default_label(&self) -> Option<String> {
         Some("Debug".to_owned())
     }
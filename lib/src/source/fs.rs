@@ -11,9 +11,6 @@ pub struct FsLineSource {
     lines: Option<io::Lines<io::BufReader<MxlFile>>>,
 
     path: PathBuf,
-
-    //TODO should be unnecessary because node should not tick if edge is finished writing.
-    done: bool,
 }
 
 impl FsLineSource {
@@ -21,7 +18,6 @@ impl FsLineSource {
         Ok(Self {
             lines: None,
             path: path.as_ref().to_owned(),
-            done: false,
         })
     }
 }
@@ -32,24 +28,19 @@ impl MxlSource for FsLineSource {
 
 impl MxlNode for FsLineSource {
     fn tick(&mut self, ctx: &mut MxlNodeCtx) -> Result<()> {
-        if !self.done {
-            if let Some(lines) = self.lines.as_mut() {
-                let next_line = lines.next();
-
-                match next_line {
-                    Some(Ok(line)) => self.send(ctx, Frame::Data(line))?,
-                    Some(Err(_err)) => self.send(ctx, Frame::Error)?,
-                    None => {
-                        self.done = true;
-                        self.send(ctx, Frame::End)?;
-                    }
-                }
-            } else {
-                let file = MxlFile::open(&self.path, MxlFileMode::Read)?;
-                let reader = io::BufReader::new(file);
-                let lines = reader.lines();
-                self.lines = Some(lines);
+        if let Some(lines) = self.lines.as_mut() {
+            let next_line = lines.next();
+
+            match next_line {
+                Some(Ok(line)) => self.send(ctx, Frame::Data(line))?,
+                Some(Err(_err)) => self.send(ctx, Frame::Error)?,
+                None => self.send(ctx, Frame::End)?,
             }
+        } else {
+            let file = MxlFile::open(&self.path, MxlFileMode::Read)?;
+            let reader = io::BufReader::new(file);
+            let lines = reader.lines();
+            self.lines = Some(lines);
         }
 
         Ok(())
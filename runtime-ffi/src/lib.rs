@@ -5,6 +5,7 @@ pub mod protos {
 mod buffer;
 
 use std::collections::HashMap;
+use std::fmt;
 
 pub use prost;
 
@@ -28,3 +29,88 @@ pub struct GraphRunState {
     pub nodes: HashMap<u32, NodeState>,
     pub edges: HashMap<String, EdgeState>,
 }
+
+/// A join node whose kind the host can't run, it's unset, `JOIN_KIND_UNKNOWN` or a value this
+/// version doesn't know
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidJoinKind {
+    pub node_id: u32,
+    pub join_kind: Option<i32>,
+}
+
+impl fmt::Display for InvalidJoinKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.join_kind {
+            Some(kind) => write!(
+                f,
+                "join node {} has unknown join kind {}",
+                self.node_id, kind
+            ),
+            None => write!(f, "join node {} has no join kind", self.node_id),
+        }
+    }
+}
+
+impl std::error::Error for InvalidJoinKind {}
+
+impl protos::VNodeInfo {
+    /// The join kind the host should run a node with, `None` for nodes that aren't joins.
+    /// Unlike the generated `join_kind()`, an unset or unknown kind on a join node is an error
+    /// rather than a default.
+    pub fn checked_join_kind(&self) -> Result<Option<protos::JoinKindProto>, InvalidJoinKind> {
+        let is_join = self.node_type == protos::VNodeTypeProto::NodeTypeJoin as i32;
+
+        if !is_join && self.join_kind.is_none() {
+            return Ok(None);
+        }
+
+        match self.join_kind.and_then(protos::JoinKindProto::from_i32) {
+            Some(kind) if is_join && kind != protos::JoinKindProto::JoinKindUnknown => {
+                Ok(Some(kind))
+            }
+            _ => Err(InvalidJoinKind {
+                node_id: self.node_id,
+                join_kind: self.join_kind,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::protos::{JoinKindProto, VNodeInfo, VNodeTypeProto};
+
+    fn node(node_type: VNodeTypeProto, join_kind: Option<i32>) -> VNodeInfo {
+        VNodeInfo {
+            node_id: 7,
+            node_type: node_type as i32,
+            join_kind,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn join_nodes_need_a_known_join_kind() {
+        let left = Some(JoinKindProto::JoinKindLeft as i32);
+
+        assert_eq!(
+            node(VNodeTypeProto::NodeTypeJoin, left).checked_join_kind(),
+            Ok(Some(JoinKindProto::JoinKindLeft))
+        );
+        assert_eq!(
+            node(VNodeTypeProto::NodeTypeSink, None).checked_join_kind(),
+            Ok(None)
+        );
+
+        for join_kind in [None, Some(JoinKindProto::JoinKindUnknown as i32), Some(42)] {
+            let err = node(VNodeTypeProto::NodeTypeJoin, join_kind)
+                .checked_join_kind()
+                .unwrap_err();
+            assert_eq!(err.join_kind, join_kind);
+        }
+
+        assert!(node(VNodeTypeProto::NodeTypeSink, left)
+            .checked_join_kind()
+            .is_err());
+    }
+}